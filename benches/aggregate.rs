@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::OnceLock;
+
+use bhtest::{
+    lookup_symbol, name_from_symbol, try_name_from_symbol, try_name_from_symbol_ci, Aggregator,
+    NetworkName, Price, StreamingAggregator, Ticker,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+const SYMBOLS: [&str; 3] = ["S1", "S2", "s3"];
+
+fn tickers(len: usize) -> Vec<Ticker> {
+    (0..len)
+        .map(|i| Ticker::new(SYMBOLS[i % SYMBOLS.len()], (i % 1000) as Price / 7.0))
+        .collect()
+}
+
+#[allow(deprecated)]
+fn aggregate_by_network(c: &mut Criterion) {
+    let mut group = c.benchmark_group("aggregate_by_network");
+    for len in [10_000, 100_000, 1_000_000] {
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &len, |b, &len| {
+            b.iter_batched(
+                || tickers(len),
+                |tickers| bhtest::aggregate_by_network(black_box(tickers)),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn count_by_network(c: &mut Criterion) {
+    let mut group = c.benchmark_group("count_by_network");
+    let tickers = tickers(1_000_000);
+    group.throughput(Throughput::Elements(tickers.len() as u64));
+    group.bench_function("count_by_network", |b| {
+        b.iter(|| bhtest::count_by_network(black_box(&tickers)))
+    });
+    group.bench_function("aggregate_by_network_ref", |b| {
+        b.iter(|| bhtest::aggregate_by_network_ref(black_box(&tickers)))
+    });
+    group.finish();
+}
+
+fn streaming_snapshot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("StreamingAggregator::snapshot");
+    for len in [10_000, 100_000, 1_000_000] {
+        let mut aggregator = StreamingAggregator::new();
+        aggregator.extend(tickers(len));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(len),
+            &aggregator,
+            |b, aggregator| {
+                b.iter(|| black_box(aggregator).snapshot());
+            },
+        );
+    }
+    group.finish();
+}
+
+fn symbol_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("name_from_symbol");
+    group.bench_function("cold", |b| {
+        b.iter_batched_ref(
+            HashMap::new,
+            |cache| name_from_symbol(black_box("S2"), cache),
+            BatchSize::SmallInput,
+        );
+    });
+    group.bench_function("warm", |b| {
+        let mut cache = HashMap::new();
+        name_from_symbol("S2", &mut cache);
+        b.iter(|| name_from_symbol(black_box("S2"), &mut cache));
+    });
+    group.finish();
+}
+
+// a feed dominated by one network, with a few unknown symbols
+fn skewed_symbols(len: usize) -> Vec<&'static str> {
+    (0..len)
+        .map(|i| match i % 100 {
+            0..=59 => "S1",
+            60..=88 => "S2",
+            89..=98 => "s3",
+            _ => "BTC",
+        })
+        .collect()
+}
+
+fn symbol_resolution(c: &mut Criterion) {
+    static MAP: OnceLock<HashMap<&str, NetworkName>> = OnceLock::new();
+
+    let symbols = skewed_symbols(10_000);
+    let mut group = c.benchmark_group("symbol_resolution");
+    group.throughput(Throughput::Elements(symbols.len() as u64));
+    group.bench_function("cache_per_call", |b| {
+        b.iter(|| {
+            let mut cache = HashMap::new();
+            for symbol in &symbols {
+                black_box(try_name_from_symbol(black_box(symbol), &mut cache));
+            }
+        });
+    });
+    group.bench_function("linear_scan", |b| {
+        b.iter(|| {
+            for symbol in &symbols {
+                let name = NetworkName::SYMBOLS
+                    .iter()
+                    .find(|(known, _)| *known == *black_box(symbol))
+                    .map(|(_, name)| *name);
+                black_box(name);
+            }
+        });
+    });
+    group.bench_function("static_map", |b| {
+        let map = MAP.get_or_init(|| NetworkName::SYMBOLS.iter().copied().collect());
+        b.iter(|| {
+            for symbol in &symbols {
+                black_box(map.get(black_box(symbol)).copied());
+            }
+        });
+    });
+    group.bench_function("lookup_symbol", |b| {
+        b.iter(|| {
+            for symbol in &symbols {
+                black_box(lookup_symbol(black_box(symbol)));
+            }
+        });
+    });
+    group.finish();
+}
+
+fn symbol_lookup_ci(c: &mut Criterion) {
+    let mut group = c.benchmark_group("name_from_symbol_ci");
+    group.bench_function("case_sensitive", |b| {
+        b.iter_batched_ref(
+            HashMap::new,
+            |cache| try_name_from_symbol(black_box("s3"), cache),
+            BatchSize::SmallInput,
+        );
+    });
+    group.bench_function("case_insensitive", |b| {
+        b.iter_batched_ref(
+            HashMap::new,
+            |cache| try_name_from_symbol_ci(black_box("S3"), cache),
+            BatchSize::SmallInput,
+        );
+    });
+    group.finish();
+}
+
+fn symbol_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookup_symbol");
+    group.bench_function("str", |b| {
+        b.iter(|| {
+            NetworkName::SYMBOLS
+                .binary_search_by(|(symbol, _)| (*symbol).cmp(black_box("s3")))
+                .ok()
+        });
+    });
+    group.bench_function("lookup_symbol", |b| {
+        b.iter(|| lookup_symbol(black_box("s3")))
+    });
+    group.finish();
+}
+
+fn aggregator_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("AggregatorBuilder::build");
+    for len in [1_000, 100_000] {
+        let symbols: Vec<_> = (0..len).map(|i| format!("SYM{i}")).collect();
+        let build = |capacity: usize| {
+            symbols
+                .iter()
+                .fold(
+                    Aggregator::builder().with_capacity(capacity),
+                    |builder, symbol| builder.add(symbol.as_str(), NetworkName::N1),
+                )
+                .build()
+                .unwrap()
+        };
+        group.bench_function(BenchmarkId::new("growing", len), |b| b.iter(|| build(0)));
+        group.bench_function(BenchmarkId::new("with_capacity", len), |b| {
+            b.iter(|| build(len))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    aggregate_by_network,
+    aggregator_build,
+    count_by_network,
+    streaming_snapshot,
+    symbol_lookup,
+    symbol_lookup_ci,
+    symbol_resolution,
+    symbol_search
+);
+criterion_main!(benches);
@@ -0,0 +1,595 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "json")]
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+};
+
+#[cfg(target_has_atomic = "64")]
+use crate::LookupStats;
+#[cfg(feature = "json")]
+use crate::ParseNetworkNameError;
+use crate::{
+    averages, try_sums, AggregateError, HashMap, NegativePricePolicy, NetworkAggregate,
+    NetworkName, OverflowPolicy, PriceValidation, Ticker, TRACK,
+};
+
+/// Aggregates tickers using a symbol to network mapping chosen at runtime instead of the built-in
+/// table.
+///
+/// ```
+/// use bhtest::{Aggregator, NetworkName, Ticker};
+///
+/// let aggregator = Aggregator::builder()
+///     .add("BTC", NetworkName::N1)
+///     .add("ETH", NetworkName::N2)
+///     .build().unwrap();
+/// let result = aggregator.aggregate(vec![Ticker::new("BTC", 1.0)]).unwrap();
+///
+/// assert_eq!((1, 1.0), result[&NetworkName::N1]);
+/// ```
+///
+/// The mapping is shared between clones, so cloning an aggregator is cheap and clones can be
+/// handed to other threads.
+#[derive(Clone, Debug)]
+pub struct Aggregator {
+    symbols: Arc<HashMap<String, NetworkName>>,
+    validation: PriceValidation,
+    // shared between clones like the mapping, `None` unless built `with_lookup_stats`
+    #[cfg(target_has_atomic = "64")]
+    stats: Option<Arc<LookupCounters>>,
+}
+
+#[cfg(target_has_atomic = "64")]
+#[derive(Debug, Default)]
+struct LookupCounters {
+    hits: AtomicU64,
+    unknown: AtomicU64,
+}
+
+impl Aggregator {
+    pub fn builder() -> AggregatorBuilder {
+        AggregatorBuilder::default()
+    }
+
+    /// Resolves `symbol` to its network in this aggregator's mapping.
+    pub fn classify(&self, symbol: &str) -> Option<NetworkName> {
+        let name = self.symbols.get(symbol).copied();
+        #[cfg(target_has_atomic = "64")]
+        if let Some(stats) = &self.stats {
+            let counter = match name {
+                Some(_) => &stats.hits,
+                None => &stats.unknown,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        name
+    }
+
+    /// Returns the counts of the lookups so far, or `None` if the aggregator wasn't built
+    /// [`with_lookup_stats`](AggregatorBuilder::with_lookup_stats).
+    ///
+    /// The mapping is a plain map without a cache in front of it, so every known symbol is a hit
+    /// and `misses` stays zero. Clones share their counts.
+    #[cfg(target_has_atomic = "64")]
+    pub fn lookup_stats(&self) -> Option<LookupStats> {
+        self.stats.as_ref().map(|stats| LookupStats {
+            hits: stats.hits.load(Ordering::Relaxed),
+            misses: 0,
+            unknown: stats.unknown.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Like [`try_aggregate_by_network`](crate::try_aggregate_by_network), but resolves symbols
+    /// with this aggregator's mapping and treats negative prices according to its
+    /// [`NegativePricePolicy`].
+    pub fn aggregate(
+        &self,
+        tickers: impl IntoIterator<Item = Ticker>,
+    ) -> Result<HashMap<NetworkName, NetworkAggregate>, AggregateError> {
+        try_sums(tickers, self.validation, |symbol| self.classify(symbol)).map(averages)
+    }
+}
+
+#[cfg(feature = "json")]
+impl Aggregator {
+    /// Reads the symbol to network mapping from the JSON file at `path`, see
+    /// [`from_json_reader`](Self::from_json_reader).
+    pub fn from_json_path(path: impl AsRef<Path>) -> Result<Self, MappingError> {
+        let file = File::open(path).map_err(MappingError::Io)?;
+        Self::from_json_reader(BufReader::new(file))
+    }
+
+    /// Reads the symbol to network mapping from a JSON object, whose keys are the ticker symbols
+    /// and whose values are the symbols of the networks they map to, e.g.
+    /// `{"BTC": "S1", "ETH": "S2"}`.
+    ///
+    /// A key may repeat with the same network, but a key repeated with another network fails with
+    /// [`MappingError::Build`], like in [`AggregatorBuilder::build`].
+    pub fn from_json_reader(reader: impl Read) -> Result<Self, MappingError> {
+        let MappingEntries(entries) =
+            serde_json::from_reader(reader).map_err(MappingError::Json)?;
+        entries
+            .into_iter()
+            .try_fold(
+                Self::builder(),
+                |builder, (symbol, network)| match network.parse() {
+                    Ok(network) => Ok(builder.add(symbol, network)),
+                    Err(source) => Err(MappingError::UnknownNetwork { symbol, source }),
+                },
+            )?
+            .build()
+            .map_err(MappingError::Build)
+    }
+}
+
+// the entries of a JSON object in input order, including repeated keys, which deserializing into
+// a map would silently drop
+#[cfg(feature = "json")]
+struct MappingEntries(Vec<(String, String)>);
+
+#[cfg(feature = "json")]
+impl<'de> serde::Deserialize<'de> for MappingEntries {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = MappingEntries;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an object of symbols and network symbols")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::with_capacity(map.size_hint().unwrap_or_default());
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(MappingEntries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+/// Uses the built-in symbol table.
+impl Default for Aggregator {
+    fn default() -> Self {
+        TRACK
+            .iter()
+            .fold(
+                Self::builder().with_capacity(TRACK.len()),
+                |builder, (symbol, name)| builder.add(*symbol, *name),
+            )
+            .build()
+            .expect("TRACK maps every symbol to a single network")
+    }
+}
+
+/// Builds an [`Aggregator`], see [`Aggregator::builder`].
+#[derive(Clone, Debug, Default)]
+pub struct AggregatorBuilder {
+    symbols: HashMap<String, NetworkName>,
+    validation: PriceValidation,
+    #[cfg(target_has_atomic = "64")]
+    lookup_stats: bool,
+    // the first symbol added with conflicting networks
+    duplicate: Option<String>,
+}
+
+impl AggregatorBuilder {
+    /// Reserves room for at least `symbols` more mappings, so adding a large table doesn't
+    /// reallocate the map as it grows.
+    pub fn with_capacity(mut self, symbols: usize) -> Self {
+        self.symbols.reserve(symbols);
+        self
+    }
+
+    /// Maps `symbol` to `network`.
+    ///
+    /// Adding the same mapping again has no effect, but mapping a symbol to a second network makes
+    /// [`build`](Self::build) fail.
+    pub fn add(mut self, symbol: impl Into<String>, network: NetworkName) -> Self {
+        let symbol = symbol.into();
+        match self.symbols.get(&symbol) {
+            Some(existing) if *existing != network => {
+                self.duplicate.get_or_insert(symbol);
+            }
+            Some(_) => {}
+            None => {
+                self.symbols.insert(symbol, network);
+            }
+        }
+        self
+    }
+
+    /// Sets what happens to negative prices, [`NegativePricePolicy::Allow`] by default.
+    pub fn negative_prices(mut self, policy: NegativePricePolicy) -> Self {
+        self.validation.negative_prices = policy;
+        self
+    }
+
+    /// Sets what happens to a ticker that would overflow its network's count,
+    /// [`OverflowPolicy::Checked`] by default.
+    pub fn count_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.validation.count_overflow = policy;
+        self
+    }
+
+    /// Makes the aggregator count its symbol lookups, see [`Aggregator::lookup_stats`].
+    ///
+    /// Aggregators built without it don't count anything.
+    #[cfg(target_has_atomic = "64")]
+    pub fn with_lookup_stats(mut self) -> Self {
+        self.lookup_stats = true;
+        self
+    }
+
+    /// Returns [`BuildError::DuplicateSymbol`] with the first symbol that was mapped to more than
+    /// one network.
+    pub fn build(self) -> Result<Aggregator, BuildError> {
+        if let Some(symbol) = self.duplicate {
+            return Err(BuildError::DuplicateSymbol(symbol));
+        }
+        Ok(Aggregator {
+            symbols: Arc::new(self.symbols),
+            validation: self.validation,
+            #[cfg(target_has_atomic = "64")]
+            stats: self.lookup_stats.then(Arc::default),
+        })
+    }
+}
+
+/// The error returned by [`AggregatorBuilder::build`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// The symbol was mapped to more than one network.
+    DuplicateSymbol(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateSymbol(symbol) => {
+                write!(f, "symbol `{symbol}` is mapped to more than one network")
+            }
+        }
+    }
+}
+
+impl core::error::Error for BuildError {}
+
+/// Aggregators by tenant id, for services where every tenant has its own symbol to network
+/// mapping.
+///
+/// ```
+/// use bhtest::{Aggregator, AggregatorSet, NetworkName, Ticker};
+///
+/// let mut set = AggregatorSet::new();
+/// set.insert("a", Aggregator::builder().add("BTC", NetworkName::N1).build().unwrap());
+/// set.insert("b", Aggregator::builder().add("BTC", NetworkName::N2).build().unwrap());
+/// let result = set.aggregate("b", vec![Ticker::new("BTC", 1.0)]).unwrap();
+///
+/// assert_eq!((1, 1.0), result[&NetworkName::N2]);
+/// assert!(set.aggregate("c", vec![]).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AggregatorSet {
+    tenants: HashMap<String, Aggregator>,
+}
+
+impl AggregatorSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the aggregator of `tenant`, and returns the one it replaced, if any.
+    pub fn insert(
+        &mut self,
+        tenant: impl Into<String>,
+        aggregator: Aggregator,
+    ) -> Option<Aggregator> {
+        self.tenants.insert(tenant.into(), aggregator)
+    }
+
+    /// Removes the aggregator of `tenant`, and returns it.
+    pub fn remove(&mut self, tenant: &str) -> Option<Aggregator> {
+        self.tenants.remove(tenant)
+    }
+
+    /// Returns the aggregator of `tenant`, e.g. to clone it for a worker thread.
+    pub fn get(&self, tenant: &str) -> Option<&Aggregator> {
+        self.tenants.get(tenant)
+    }
+
+    /// Aggregates `tickers` with the aggregator of `tenant`, see [`Aggregator::aggregate`].
+    ///
+    /// Returns [`AggregateError::UnknownTenant`] if there's no aggregator for `tenant`.
+    pub fn aggregate(
+        &self,
+        tenant: &str,
+        tickers: impl IntoIterator<Item = Ticker>,
+    ) -> Result<HashMap<NetworkName, NetworkAggregate>, AggregateError> {
+        self.get(tenant)
+            .ok_or_else(|| AggregateError::UnknownTenant(String::from(tenant)))?
+            .aggregate(tickers)
+    }
+}
+
+/// The error returned when loading an [`Aggregator`] mapping from JSON.
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub enum MappingError {
+    Io(io::Error),
+    /// The input isn't a JSON object of strings.
+    Json(serde_json::Error),
+    /// A symbol maps to a network that doesn't exist.
+    UnknownNetwork {
+        symbol: String,
+        source: ParseNetworkNameError,
+    },
+    /// The mapping can't be built, e.g. because a symbol maps to two networks.
+    Build(BuildError),
+}
+
+#[cfg(feature = "json")]
+impl fmt::Display for MappingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read symbol mapping: {err}"),
+            Self::Json(err) => write!(f, "invalid symbol mapping: {err}"),
+            Self::UnknownNetwork { symbol, source } => write!(f, "symbol `{symbol}`: {source}"),
+            Self::Build(err) => write!(f, "invalid symbol mapping: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl std::error::Error for MappingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::UnknownNetwork { source, .. } => Some(source),
+            Self::Build(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aggregator_custom_symbols() {
+        let aggregator = Aggregator::builder()
+            .add("BTC", NetworkName::N1)
+            .add("ETH", NetworkName::N2)
+            .add("WETH", NetworkName::N2)
+            .build()
+            .unwrap();
+        let tickers = vec![
+            Ticker::new("BTC", 1.0),
+            Ticker::new("ETH", 2.0),
+            Ticker::new("WETH", 4.0),
+        ];
+        let result = aggregator.aggregate(tickers).unwrap();
+
+        assert_eq!((1, 1.0), result[&NetworkName::N1]);
+        assert_eq!((2, 3.0), result[&NetworkName::N2]);
+        assert_eq!(None, aggregator.classify("S1"));
+    }
+
+    #[test]
+    fn aggregator_builder_with_capacity() {
+        let builder = Aggregator::builder().with_capacity(100);
+        assert!(builder.symbols.capacity() >= 100);
+
+        let aggregator = builder.add("BTC", NetworkName::N1).build().unwrap();
+        assert_eq!(Some(NetworkName::N1), aggregator.classify("BTC"));
+    }
+
+    #[test]
+    fn aggregator_builder_duplicate_symbol() {
+        let aggregator = Aggregator::builder()
+            .add("BTC", NetworkName::N1)
+            .add("BTC", NetworkName::N1)
+            .build()
+            .unwrap();
+        assert_eq!(Some(NetworkName::N1), aggregator.classify("BTC"));
+
+        assert_eq!(
+            Err(BuildError::DuplicateSymbol(String::from("ETH"))),
+            Aggregator::builder()
+                .add("ETH", NetworkName::N1)
+                .add("BTC", NetworkName::N1)
+                .add("ETH", NetworkName::N2)
+                .add("BTC", NetworkName::N3)
+                .build()
+                .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn aggregator_unknown_symbol() {
+        let aggregator = Aggregator::builder()
+            .add("BTC", NetworkName::N1)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            &AggregateError::UnknownSymbol(String::from("S1")),
+            aggregator
+                .aggregate(vec![Ticker::new("S1", 1.0)])
+                .unwrap_err()
+                .reason()
+        );
+    }
+
+    #[test]
+    fn aggregator_negative_prices() {
+        let tickers = || vec![Ticker::new("BTC", 1.0), Ticker::new("BTC", -1.0)];
+        let builder = || Aggregator::builder().add("BTC", NetworkName::N1);
+
+        let allowed = builder().build().unwrap().aggregate(tickers()).unwrap();
+        assert_eq!((2, 0.0), allowed[&NetworkName::N1]);
+
+        let skipped = builder()
+            .negative_prices(NegativePricePolicy::Skip)
+            .build()
+            .unwrap()
+            .aggregate(tickers())
+            .unwrap();
+        assert_eq!((1, 1.0), skipped[&NetworkName::N1]);
+
+        let rejected = builder()
+            .negative_prices(NegativePricePolicy::Reject)
+            .build()
+            .unwrap()
+            .aggregate(tickers());
+        assert!(matches!(
+            rejected.unwrap_err().reason(),
+            AggregateError::InvalidPrice { .. }
+        ));
+    }
+
+    #[cfg(target_has_atomic = "64")]
+    #[test]
+    fn aggregator_lookup_stats() {
+        let builder = || Aggregator::builder().add("BTC", NetworkName::N1);
+        assert_eq!(None, builder().build().unwrap().lookup_stats());
+
+        let aggregator = builder().with_lookup_stats().build().unwrap();
+        assert_eq!(Some(LookupStats::default()), aggregator.lookup_stats());
+
+        let tickers = vec![Ticker::new("BTC", 1.0), Ticker::new("BTC", 2.0)];
+        aggregator.aggregate(tickers).unwrap();
+        aggregator
+            .clone()
+            .aggregate(vec![Ticker::new("ETH", 1.0)])
+            .unwrap_err();
+        assert_eq!(
+            Some(LookupStats {
+                hits: 2,
+                misses: 0,
+                unknown: 1,
+            }),
+            aggregator.lookup_stats()
+        );
+    }
+
+    #[test]
+    fn aggregator_default_uses_track() {
+        let aggregator = Aggregator::default();
+
+        for (symbol, name) in TRACK {
+            assert_eq!(Some(*name), aggregator.classify(symbol));
+        }
+    }
+
+    #[test]
+    fn aggregator_clone_shares_symbols() {
+        let aggregator = Aggregator::builder()
+            .add("BTC", NetworkName::N1)
+            .build()
+            .unwrap();
+        let clone = aggregator.clone();
+
+        assert!(Arc::ptr_eq(&aggregator.symbols, &clone.symbols));
+    }
+
+    #[test]
+    fn aggregator_set_dispatches_by_tenant() {
+        let mut set = AggregatorSet::new();
+        set.insert(
+            "a",
+            Aggregator::builder()
+                .add("BTC", NetworkName::N1)
+                .build()
+                .unwrap(),
+        );
+        set.insert(
+            "b",
+            Aggregator::builder()
+                .add("BTC", NetworkName::N2)
+                .build()
+                .unwrap(),
+        );
+        let tickers = || vec![Ticker::new("BTC", 1.0)];
+
+        assert_eq!(
+            (1, 1.0),
+            set.aggregate("a", tickers()).unwrap()[&NetworkName::N1]
+        );
+        assert_eq!(
+            (1, 1.0),
+            set.aggregate("b", tickers()).unwrap()[&NetworkName::N2]
+        );
+        assert_eq!(
+            Err(AggregateError::UnknownTenant(String::from("c"))),
+            set.aggregate("c", tickers())
+        );
+
+        assert!(set.remove("a").is_some());
+        assert!(set.get("a").is_none());
+        assert!(set.aggregate("a", tickers()).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn aggregator_from_json_reader() {
+        let aggregator =
+            Aggregator::from_json_reader(r#"{"BTC": "S1", "ETH": "S2"}"#.as_bytes()).unwrap();
+
+        assert_eq!(Some(NetworkName::N1), aggregator.classify("BTC"));
+        assert_eq!(Some(NetworkName::N2), aggregator.classify("ETH"));
+        assert_eq!(None, aggregator.classify("S1"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn aggregator_from_json_reader_unknown_network() {
+        let err = Aggregator::from_json_reader(r#"{"BTC": "N9"}"#.as_bytes()).unwrap_err();
+
+        assert_eq!("symbol `BTC`: unknown network symbol `N9`", err.to_string());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn aggregator_from_json_reader_duplicate_symbol() {
+        let err = Aggregator::from_json_reader(r#"{"BTC": "S1", "BTC": "S2"}"#.as_bytes());
+
+        assert!(matches!(
+            err,
+            Err(MappingError::Build(BuildError::DuplicateSymbol(symbol))) if symbol == "BTC"
+        ));
+
+        let aggregator =
+            Aggregator::from_json_reader(r#"{"BTC": "S1", "BTC": "S1"}"#.as_bytes()).unwrap();
+        assert_eq!(Some(NetworkName::N1), aggregator.classify("BTC"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn aggregator_from_json_path() {
+        let path = std::env::temp_dir().join("bhtest_aggregator_from_json_path.json");
+        std::fs::write(&path, r#"{"BTC": "s3"}"#).unwrap();
+        let aggregator = Aggregator::from_json_path(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(Some(NetworkName::N3), aggregator.unwrap().classify("BTC"));
+        assert!(matches!(
+            Aggregator::from_json_path(&path),
+            Err(MappingError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+use alloc::string::String;
+
+use crate::{expect_network, HashMap, NetworkName};
+
+/// A [`Ticker`](crate::Ticker) with its price in integer minor units, e.g. cents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CentsTicker {
+    pub symbol: String,
+    pub price_cents: i64,
+}
+
+impl CentsTicker {
+    pub fn new(symbol: impl Into<String>, price_cents: i64) -> Self {
+        Self {
+            symbol: symbol.into(),
+            price_cents,
+        }
+    }
+}
+
+/// The ticker count and the exact sum of the prices of a network, in the minor units of the
+/// tickers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CentsAggregate {
+    pub count: u32,
+    pub sum_cents: i64,
+}
+
+impl CentsAggregate {
+    /// Returns the average price as an exact fraction `(numerator, denominator)` of minor units,
+    /// in lowest terms and with a positive denominator, or `None` if `count` is zero.
+    pub fn average(&self) -> Option<(i64, i64)> {
+        if self.count == 0 {
+            return None;
+        }
+        let divisor = gcd(self.sum_cents.unsigned_abs(), u64::from(self.count));
+        // the divisor divides the count, so it's positive and both fit into an `i64`
+        let divisor = divisor as i64;
+        Some((self.sum_cents / divisor, i64::from(self.count) / divisor))
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Groups `tickers` by network and sums their prices exactly in `i64`.
+///
+/// A network's prices can add up to `i64::MAX` minor units, which is about 92 quadrillion units
+/// with cents, before the sum overflows.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known, or if a network's sum overflows `i64`.
+pub fn aggregate_cents_by_network(
+    tickers: impl IntoIterator<Item = CentsTicker>,
+) -> HashMap<NetworkName, CentsAggregate> {
+    tickers.into_iter().fold(HashMap::new(), |mut res, val| {
+        let entry = res
+            .entry(expect_network(&val.symbol))
+            .or_insert(CentsAggregate {
+                count: 0,
+                sum_cents: 0,
+            });
+        entry.count = entry.count.checked_add(1).expect("count doesn't overflow");
+        entry.sum_cents = entry
+            .sum_cents
+            .checked_add(val.price_cents)
+            .expect("sum doesn't overflow");
+        res
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aggregate_cents_by_network_exact() {
+        let tickers = vec![
+            CentsTicker::new("S1", 100),
+            CentsTicker::new("S1", 0),
+            CentsTicker::new("S1", 100),
+            CentsTicker::new("S2", -150),
+            CentsTicker::new("S2", 50),
+        ];
+        let result = aggregate_cents_by_network(tickers);
+
+        let n1 = result[&NetworkName::N1];
+        assert_eq!(
+            CentsAggregate {
+                count: 3,
+                sum_cents: 200
+            },
+            n1
+        );
+        assert_eq!(Some((200, 3)), n1.average());
+        assert_eq!(Some((-50, 1)), result[&NetworkName::N2].average());
+    }
+
+    #[test]
+    fn cents_aggregate_average_empty() {
+        let empty = CentsAggregate {
+            count: 0,
+            sum_cents: 0,
+        };
+
+        assert_eq!(None, empty.average());
+    }
+
+    #[test]
+    #[should_panic(expected = "sum doesn't overflow")]
+    fn aggregate_cents_by_network_overflow() {
+        aggregate_cents_by_network(vec![
+            CentsTicker::new("S1", i64::MAX),
+            CentsTicker::new("S1", 1),
+        ]);
+    }
+
+    #[test]
+    fn aggregate_cents_by_network_empty() {
+        assert!(aggregate_cents_by_network(vec![]).is_empty());
+    }
+}
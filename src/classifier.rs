@@ -0,0 +1,200 @@
+use alloc::borrow::Cow;
+
+use crate::{
+    averages, lookup_symbol, try_sums, AggregateError, Aggregator, HashMap, NetworkAggregate,
+    NetworkName, PriceValidation, Ticker,
+};
+
+/// Resolves ticker symbols to networks, see [`aggregate_with_classifier`].
+///
+/// Besides [`DefaultClassifier`], [`PrefixClassifier`] and [`Aggregator`], every
+/// `Fn(&str) -> Option<NetworkName>` is a classifier, so fuzzy, pattern-based or remote lookups
+/// can be plugged in as closures.
+pub trait SymbolClassifier {
+    /// Returns the network `symbol` belongs to, or `None` if it doesn't belong to any.
+    fn classify(&self, symbol: &str) -> Option<NetworkName>;
+}
+
+/// Classifies symbols with the built-in table, like [`lookup_symbol`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultClassifier;
+
+impl SymbolClassifier for DefaultClassifier {
+    fn classify(&self, symbol: &str) -> Option<NetworkName> {
+        lookup_symbol(symbol)
+    }
+}
+
+/// Classifies symbols like `"S1:BTC-USD"` by the part before a delimiter, here `':'`, with the
+/// built-in table.
+///
+/// A symbol without the delimiter is classified as a whole, like with [`DefaultClassifier`]. Only
+/// the first delimiter splits, so `"S1:BTC:USD"` has the prefix `"S1"` and the rest `"BTC:USD"`.
+#[derive(Clone, Copy, Debug)]
+pub struct PrefixClassifier {
+    delimiter: char,
+}
+
+impl PrefixClassifier {
+    pub fn new(delimiter: char) -> Self {
+        Self { delimiter }
+    }
+
+    /// Splits `symbol` into the prefix that determines its network and the rest after the
+    /// delimiter, e.g. for per-symbol breakdowns. The rest is `None` without a delimiter.
+    pub fn split<'a>(&self, symbol: &'a str) -> (&'a str, Option<&'a str>) {
+        match symbol.split_once(self.delimiter) {
+            Some((prefix, rest)) => (prefix, Some(rest)),
+            None => (symbol, None),
+        }
+    }
+}
+
+impl SymbolClassifier for PrefixClassifier {
+    fn classify(&self, symbol: &str) -> Option<NetworkName> {
+        lookup_symbol(self.split(symbol).0)
+    }
+}
+
+impl SymbolClassifier for Aggregator {
+    fn classify(&self, symbol: &str) -> Option<NetworkName> {
+        Aggregator::classify(self, symbol)
+    }
+}
+
+impl<F: Fn(&str) -> Option<NetworkName>> SymbolClassifier for F {
+    fn classify(&self, symbol: &str) -> Option<NetworkName> {
+        self(symbol)
+    }
+}
+
+/// Like [`try_aggregate_by_network`](crate::try_aggregate_by_network), but resolves symbols with
+/// `classifier`.
+///
+/// With [`DefaultClassifier`] this is the same as `try_aggregate_by_network`.
+pub fn aggregate_with_classifier<C: SymbolClassifier + ?Sized>(
+    tickers: impl IntoIterator<Item = Ticker>,
+    classifier: &C,
+) -> Result<HashMap<NetworkName, NetworkAggregate>, AggregateError> {
+    try_sums(tickers, PriceValidation::default(), |symbol| {
+        classifier.classify(symbol)
+    })
+    .map(averages)
+}
+
+/// Like [`try_aggregate_by_network`](crate::try_aggregate_by_network), but maps every symbol with
+/// `normalize` before looking it up, e.g. to uppercase it or to strip an exchange suffix.
+///
+/// `normalize` runs once per ticker. Returning [`Cow::Borrowed`] for symbols that don't need a
+/// change, e.g. a subslice of the argument, avoids allocating for them. Errors report the symbol
+/// as it was before normalization.
+pub fn aggregate_with_normalizer<F: Fn(&str) -> Cow<'_, str>>(
+    tickers: impl IntoIterator<Item = Ticker>,
+    normalize: F,
+) -> Result<HashMap<NetworkName, NetworkAggregate>, AggregateError> {
+    try_sums(tickers, PriceValidation::default(), |symbol| {
+        lookup_symbol(&normalize(symbol))
+    })
+    .map(averages)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::try_aggregate_by_network;
+    use alloc::string::String;
+
+    fn tickers() -> Vec<Ticker> {
+        vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("S2", 0.2),
+            Ticker::new("S1", 0.3),
+        ]
+    }
+
+    #[test]
+    fn aggregate_with_classifier_default() {
+        assert_eq!(
+            try_aggregate_by_network(tickers()),
+            aggregate_with_classifier(tickers(), &DefaultClassifier)
+        );
+    }
+
+    #[test]
+    fn prefix_classifier_split() {
+        let classifier = PrefixClassifier::new(':');
+
+        assert_eq!(("S1", Some("BTC-USD")), classifier.split("S1:BTC-USD"));
+        assert_eq!(("S1", Some("BTC:USD")), classifier.split("S1:BTC:USD"));
+        assert_eq!(("S1", Some("")), classifier.split("S1:"));
+        assert_eq!(("S1", None), classifier.split("S1"));
+    }
+
+    #[test]
+    fn prefix_classifier_classify() {
+        let classifier = PrefixClassifier::new(':');
+
+        assert_eq!(Some(NetworkName::N1), classifier.classify("S1:BTC-USD"));
+        assert_eq!(Some(NetworkName::N3), classifier.classify("s3"));
+        assert_eq!(None, classifier.classify("S1-BTC"));
+        assert_eq!(None, classifier.classify("BTC:S1"));
+        assert_eq!(
+            Some(NetworkName::N2),
+            PrefixClassifier::new('/').classify("S2/ETH")
+        );
+
+        let tickers = vec![Ticker::new("S1:BTC", 1.0), Ticker::new("S1:ETH", 3.0)];
+        let result = aggregate_with_classifier(tickers, &classifier).unwrap();
+        assert_eq!((2, 2.0), result[&NetworkName::N1]);
+    }
+
+    #[test]
+    fn aggregate_with_normalizer_correct() {
+        let tickers = vec![
+            Ticker::new("S1.BINANCE", 1.0),
+            Ticker::new("S1", 3.0),
+            Ticker::new("S2.KRAKEN", 2.0),
+        ];
+        let result = aggregate_with_normalizer(tickers, |symbol| {
+            Cow::Borrowed(symbol.split_once('.').map_or(symbol, |(prefix, _)| prefix))
+        })
+        .unwrap();
+
+        assert_eq!((2, 2.0), result[&NetworkName::N1]);
+        assert_eq!((1, 2.0), result[&NetworkName::N2]);
+
+        let err = aggregate_with_normalizer(vec![Ticker::new("s1", 1.0)], |symbol| {
+            Cow::Owned(symbol.to_uppercase() + "X")
+        })
+        .unwrap_err();
+        assert_eq!(
+            &AggregateError::UnknownSymbol(String::from("s1")),
+            err.reason()
+        );
+    }
+
+    #[test]
+    fn aggregate_with_classifier_custom() {
+        // everything with a known prefix, e.g. `S1-PERP`, belongs to the prefix's network
+        struct Prefix;
+
+        impl SymbolClassifier for Prefix {
+            fn classify(&self, symbol: &str) -> Option<NetworkName> {
+                lookup_symbol(symbol.split('-').next()?)
+            }
+        }
+
+        let tickers = vec![Ticker::new("S1-PERP", 0.1), Ticker::new("S1", 0.3)];
+        let result = aggregate_with_classifier(tickers, &Prefix).unwrap();
+        assert_eq!(2, result[&NetworkName::N1].0);
+
+        let err = aggregate_with_classifier(vec![Ticker::new("S2", 1.0)], &|symbol: &str| {
+            (symbol == "S1").then_some(NetworkName::N1)
+        })
+        .unwrap_err();
+        assert_eq!(
+            &AggregateError::UnknownSymbol(String::from("S2")),
+            err.reason()
+        );
+    }
+}
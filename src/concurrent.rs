@@ -0,0 +1,163 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{
+    lookup_symbol, AggregateError, HashMap, NetworkAggregate, NetworkName, Price, PriceValidation,
+    Ticker, METADATA,
+};
+
+/// Aggregates tickers pushed from many threads at once, without a lock.
+///
+/// Every network keeps its count and its `f64` price sum in an [`AtomicU64`] each, so producers
+/// only contend when they push to the same network at the same time. The sum is updated with a
+/// compare-and-swap loop on the bits of the `f64`.
+///
+/// # Ordering
+///
+/// A push updates the sum with [`Relaxed`](Ordering::Relaxed) ordering and then increments the
+/// count with [`Release`](Ordering::Release) ordering. A [`snapshot`](Self::snapshot) loads the
+/// count with [`Acquire`](Ordering::Acquire) ordering before loading the sum, so the sum it sees
+/// includes the price of every ticker it counts. Pushes running concurrently with a snapshot may
+/// already be in its sum but not yet in its count, which skews the average by their prices.
+/// Networks are loaded one after another, so a snapshot isn't a single point in time across
+/// networks either.
+///
+/// Once every push happened-before the snapshot, e.g. because the producer threads were joined,
+/// the snapshot is exact. Unlike [`PartialAggregate`](crate::PartialAggregate) the sum isn't
+/// compensated, because the sum and its compensation couldn't be updated as one, so the last bits
+/// of the average can depend on the order of the pushes.
+#[derive(Debug)]
+pub struct ConcurrentAggregator {
+    // indexed by variant, which is also the order of `METADATA`
+    networks: [NetworkSums; METADATA.len()],
+}
+
+#[derive(Debug, Default)]
+struct NetworkSums {
+    count: AtomicU64,
+    // the bits of an `f64`
+    sum: AtomicU64,
+}
+
+impl ConcurrentAggregator {
+    pub fn new() -> Self {
+        Self {
+            networks: core::array::from_fn(|_| NetworkSums::default()),
+        }
+    }
+
+    /// Folds `ticker` into the running aggregate.
+    ///
+    /// Tickers are validated like in [`try_aggregate_by_network`](crate::try_aggregate_by_network).
+    /// A rejected ticker leaves the aggregate unchanged.
+    pub fn push(&self, ticker: Ticker) -> Result<(), AggregateError> {
+        let Some(name) = lookup_symbol(&ticker.symbol) else {
+            return Err(AggregateError::UnknownSymbol(ticker.symbol.into_owned()));
+        };
+        let validation = PriceValidation::default();
+        if !validation.accepts(ticker.price) {
+            return Err(AggregateError::InvalidPrice {
+                symbol: ticker.symbol.into_owned(),
+                price: ticker.price,
+            });
+        }
+        if validation.skips(ticker.price) {
+            return Ok(());
+        }
+
+        let sums = &self.networks[name as usize];
+        // the closure always returns `Some`, so the update can't fail
+        let _ = sums
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + f64::from(ticker.price)).to_bits())
+            });
+        // publishes the sum to snapshots that see this increment, see the type docs
+        sums.count.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns the count and average price per network of everything pushed so far.
+    ///
+    /// See the [type docs](Self#ordering) for what a snapshot taken during pushes sees. Returns
+    /// [`AggregateError::CountOverflow`] if a network's count no longer fits into a
+    /// [`NetworkAggregate`].
+    pub fn snapshot(&self) -> Result<HashMap<NetworkName, NetworkAggregate>, AggregateError> {
+        let mut result = HashMap::new();
+        for ((name, _, _), sums) in METADATA.iter().zip(&self.networks) {
+            let count = sums.count.load(Ordering::Acquire);
+            if count == 0 {
+                continue;
+            }
+            let sum = f64::from_bits(sums.sum.load(Ordering::Relaxed));
+            let count = u32::try_from(count).map_err(|_| AggregateError::CountOverflow(*name))?;
+            result.insert(*name, (count, (sum / f64::from(count)) as Price));
+        }
+        Ok(result)
+    }
+}
+
+impl Default for ConcurrentAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::String;
+
+    #[test]
+    fn concurrent_aggregator_snapshot() {
+        let aggregator = ConcurrentAggregator::new();
+        aggregator.push(Ticker::new("S1", 1.0)).unwrap();
+        aggregator.push(Ticker::new("S1", 2.0)).unwrap();
+        aggregator.push(Ticker::new("S2", 4.0)).unwrap();
+
+        assert_eq!(
+            HashMap::from([(NetworkName::N1, (2, 1.5)), (NetworkName::N2, (1, 4.0))]),
+            aggregator.snapshot().unwrap()
+        );
+    }
+
+    #[test]
+    fn concurrent_aggregator_empty() {
+        assert!(ConcurrentAggregator::new().snapshot().unwrap().is_empty());
+    }
+
+    #[test]
+    fn concurrent_aggregator_rejects_invalid_tickers() {
+        let aggregator = ConcurrentAggregator::new();
+
+        assert_eq!(
+            Err(AggregateError::UnknownSymbol(String::from("foo"))),
+            aggregator.push(Ticker::new("foo", 1.0))
+        );
+        assert!(aggregator.push(Ticker::new("S1", Price::NAN)).is_err());
+        assert!(aggregator.snapshot().unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn concurrent_aggregator_many_threads() {
+        let aggregator = ConcurrentAggregator::new();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for i in 0..1000 {
+                        let symbol = if i % 2 == 0 { "S1" } else { "S2" };
+                        aggregator.push(Ticker::new(symbol, 2.0)).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(
+            HashMap::from([
+                (NetworkName::N1, (4000, 2.0)),
+                (NetworkName::N2, (4000, 2.0))
+            ]),
+            aggregator.snapshot().unwrap()
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+use std::num::ParseFloatError;
+
+use crate::Ticker;
+
+/// Reads `symbol,price` rows into tickers.
+///
+/// The first line is a header and is skipped, as are blank lines. Fields are trimmed, so both
+/// `S1, 0.1` and CRLF line endings are accepted.
+pub fn from_csv_reader<R: Read>(reader: R) -> Result<Vec<Ticker>, CsvError> {
+    let mut tickers = Vec::new();
+
+    for (index, line) in BufReader::new(reader).lines().enumerate().skip(1) {
+        let line = line.map_err(CsvError::Io)?;
+        let line_number = index + 1;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split(',').map(str::trim);
+        let (Some(symbol), Some(price), None) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(CsvError::MalformedLine { line: line_number });
+        };
+        let price = price.parse().map_err(|source| CsvError::InvalidPrice {
+            line: line_number,
+            source,
+        })?;
+
+        tickers.push(Ticker::new(symbol, price));
+    }
+
+    Ok(tickers)
+}
+
+/// The error returned by [`from_csv_reader`]. Line numbers are 1-based and count the header.
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    /// The line doesn't have exactly two fields.
+    MalformedLine {
+        line: usize,
+    },
+    /// The price field isn't a number.
+    InvalidPrice {
+        line: usize,
+        source: ParseFloatError,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read CSV: {err}"),
+            Self::MalformedLine { line } => {
+                write!(f, "line {line}: expected `symbol,price`")
+            }
+            Self::InvalidPrice { line, source } => {
+                write!(f, "line {line}: invalid price: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::MalformedLine { .. } => None,
+            Self::InvalidPrice { source, .. } => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_csv_reader_correct() {
+        let csv = "symbol,price\nS1,0.1\r\n\nS2, 0.2\n";
+        let tickers = from_csv_reader(csv.as_bytes()).unwrap();
+
+        assert_eq!(2, tickers.len());
+        assert_eq!("S1", tickers[0].symbol);
+        assert_eq!(0.1, tickers[0].price);
+        assert_eq!("S2", tickers[1].symbol);
+        assert_eq!(0.2, tickers[1].price);
+    }
+
+    #[test]
+    fn from_csv_reader_invalid_price() {
+        let csv = "symbol,price\nS1,0.1\nS2,\n";
+
+        assert!(matches!(
+            from_csv_reader(csv.as_bytes()),
+            Err(CsvError::InvalidPrice { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn from_csv_reader_malformed_line() {
+        let csv = "symbol,price\nS1\n";
+
+        assert!(matches!(
+            from_csv_reader(csv.as_bytes()),
+            Err(CsvError::MalformedLine { line: 2 })
+        ));
+    }
+}
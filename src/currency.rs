@@ -0,0 +1,136 @@
+use alloc::string::String;
+use core::fmt;
+
+use crate::{
+    lookup_symbol, AggregateError, HashMap, NetworkName, PartialAggregate, Price, PriceValidation,
+    Ticker,
+};
+
+/// The currency a [`Ticker`]'s price is quoted in, e.g. `Currency::from("EUR")`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Currency(pub String);
+
+impl From<&str> for Currency {
+    fn from(code: &str) -> Self {
+        Self(code.into())
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Converts every price into the base currency with `rates` and returns the average price per
+/// network.
+///
+/// A rate is the price of one unit of its currency in the base currency. Tickers without a
+/// currency are taken to be in the base currency already. Fails with
+/// [`AggregateError::UnknownCurrency`] on the first currency missing from `rates`, and with
+/// [`AggregateError::UnknownSymbol`] on the first unknown symbol.
+///
+/// The converted prices are validated like in
+/// [`try_aggregate_by_network`](crate::try_aggregate_by_network), and a rate that isn't positive
+/// and finite fails the ticker converted with it too, both with [`AggregateError::InvalidPrice`]
+/// and the converted price.
+pub fn aggregate_normalized(
+    tickers: impl IntoIterator<Item = Ticker>,
+    rates: &HashMap<Currency, Price>,
+) -> Result<HashMap<NetworkName, Price>, AggregateError> {
+    let mut sums = HashMap::<NetworkName, PartialAggregate>::new();
+    for val in tickers {
+        let Some(name) = lookup_symbol(&val.symbol) else {
+            return Err(AggregateError::UnknownSymbol(val.symbol.into_owned()));
+        };
+        let (price, valid_rate) = match val.currency {
+            Some(currency) => {
+                let Some(rate) = rates.get(&currency) else {
+                    return Err(AggregateError::UnknownCurrency(currency));
+                };
+                (val.price * rate, rate.is_finite() && *rate > 0.0)
+            }
+            None => (val.price, true),
+        };
+        if !valid_rate || !PriceValidation::default().accepts(price) {
+            return Err(AggregateError::InvalidPrice {
+                symbol: val.symbol.into_owned(),
+                price,
+            });
+        }
+        sums.entry(name).or_default().add_price(price);
+    }
+
+    let result = sums
+        .into_iter()
+        .filter_map(|(name, partial)| Some((name, partial.finalize()?)))
+        .collect();
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aggregate_normalized_converts_prices() {
+        let rates = HashMap::from([(Currency::from("EUR"), 2.0), (Currency::from("JPY"), 0.5)]);
+        let tickers = vec![
+            Ticker::new("S1", 1.0).with_currency("EUR"),
+            Ticker::new("S1", 4.0).with_currency("JPY"),
+            Ticker::new("S1", 3.0),
+        ];
+        let result = aggregate_normalized(tickers, &rates).unwrap();
+
+        assert_eq!(HashMap::from([(NetworkName::N1, 7.0 / 3.0)]), result);
+    }
+
+    #[test]
+    fn aggregate_normalized_unknown_currency() {
+        let tickers = vec![Ticker::new("S1", 1.0).with_currency("GBP")];
+
+        assert_eq!(
+            Err(AggregateError::UnknownCurrency(Currency::from("GBP"))),
+            aggregate_normalized(tickers, &HashMap::new())
+        );
+    }
+
+    #[test]
+    fn aggregate_normalized_invalid_price() {
+        let invalid = |ticker: Ticker, rate| {
+            let rates = HashMap::from([(Currency::from("EUR"), rate)]);
+            matches!(
+                aggregate_normalized(vec![ticker], &rates),
+                Err(AggregateError::InvalidPrice { .. })
+            )
+        };
+
+        assert!(invalid(Ticker::new("S1", Price::NAN), 1.0));
+        assert!(invalid(Ticker::new("S1", Price::INFINITY), 1.0));
+        assert!(invalid(
+            Ticker::new("S1", Price::NAN).with_currency("EUR"),
+            1.0
+        ));
+        assert!(invalid(
+            Ticker::new("S1", 1.0).with_currency("EUR"),
+            Price::NAN
+        ));
+        assert!(invalid(
+            Ticker::new("S1", 1.0).with_currency("EUR"),
+            Price::INFINITY
+        ));
+        assert!(invalid(Ticker::new("S1", 1.0).with_currency("EUR"), 0.0));
+        assert!(invalid(Ticker::new("S1", 1.0).with_currency("EUR"), -2.0));
+        // negative prices are allowed, like in `try_aggregate_by_network`
+        assert!(!invalid(Ticker::new("S1", -1.0).with_currency("EUR"), 2.0));
+    }
+
+    #[test]
+    fn aggregate_normalized_empty() {
+        assert_eq!(
+            Ok(HashMap::new()),
+            aggregate_normalized(vec![], &HashMap::new())
+        );
+    }
+}
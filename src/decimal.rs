@@ -0,0 +1,90 @@
+use alloc::string::String;
+
+use rust_decimal::Decimal;
+
+use crate::{expect_network, HashMap, NetworkName};
+
+/// A [`Ticker`](crate::Ticker) with an exact decimal price.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecimalTicker {
+    pub symbol: String,
+    pub price: Decimal,
+}
+
+impl DecimalTicker {
+    pub fn new(symbol: impl Into<String>, price: Decimal) -> Self {
+        Self {
+            symbol: symbol.into(),
+            price,
+        }
+    }
+}
+
+/// Like [`NetworkAggregate`](crate::NetworkAggregate), with an exact average price.
+pub type DecimalAggregate = (u32, Decimal);
+
+/// Groups decimal `tickers` by network and returns the ticker count and the average price per
+/// network.
+///
+/// The prices are summed exactly. The division by the count is exact whenever the average fits
+/// into the 28 digits of a [`Decimal`], otherwise it's rounded to the nearest representable value,
+/// with ties rounded to even.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known, or if a sum exceeds the range of [`Decimal`].
+pub fn aggregate_decimal_by_network(
+    tickers: impl IntoIterator<Item = DecimalTicker>,
+) -> HashMap<NetworkName, DecimalAggregate> {
+    tickers
+        .into_iter()
+        .fold(
+            HashMap::<NetworkName, (u32, Decimal)>::new(),
+            |mut res, val| {
+                let entry = res.entry(expect_network(&val.symbol)).or_default();
+                entry.0 = entry.0.checked_add(1).expect("count doesn't overflow");
+                entry.1 += val.price;
+                res
+            },
+        )
+        .into_iter()
+        .map(|(key, (count, sum))| (key, (count, sum / Decimal::from(count))))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aggregate_decimal_by_network_exact() {
+        let tickers = vec![
+            DecimalTicker::new("S1", Decimal::new(1, 1)),
+            DecimalTicker::new("S1", Decimal::new(3, 1)),
+            DecimalTicker::new("S2", Decimal::new(1, 1)),
+            DecimalTicker::new("S2", Decimal::new(2, 1)),
+            DecimalTicker::new("S2", Decimal::new(3, 1)),
+        ];
+        let result = aggregate_decimal_by_network(tickers);
+
+        assert_eq!((2, Decimal::new(2, 1)), result[&NetworkName::N1]);
+        assert_eq!((3, Decimal::new(2, 1)), result[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn aggregate_decimal_by_network_rounds_inexact_average() {
+        let tickers = vec![
+            DecimalTicker::new("S1", Decimal::ONE),
+            DecimalTicker::new("S1", Decimal::ZERO),
+            DecimalTicker::new("S1", Decimal::ONE),
+        ];
+        let (_, avg) = aggregate_decimal_by_network(tickers)[&NetworkName::N1];
+
+        assert_eq!("0.6666666666666666666666666667", avg.to_string());
+    }
+
+    #[test]
+    fn aggregate_decimal_by_network_empty() {
+        assert!(aggregate_decimal_by_network(vec![]).is_empty());
+    }
+}
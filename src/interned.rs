@@ -0,0 +1,105 @@
+use crate::{add_to_sums, averages, HashMap, NetworkAggregate, NetworkName, Price, Ticker, TRACK};
+
+/// A known symbol, stored as its index in the symbol table instead of as a string.
+///
+/// Comparing, hashing and resolving ids never touches the symbol's characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SymbolId(u16);
+
+impl SymbolId {
+    /// Returns the id of `symbol`, or `None` if `symbol` isn't known.
+    pub fn new(symbol: &str) -> Option<Self> {
+        let index = TRACK.binary_search_by(|(sym, _)| (*sym).cmp(symbol)).ok()?;
+        Some(Self(index as u16))
+    }
+
+    /// Returns the symbol this id was created from.
+    pub fn as_str(self) -> &'static str {
+        TRACK[usize::from(self.0)].0
+    }
+
+    /// Returns the network the symbol belongs to.
+    pub fn network(self) -> NetworkName {
+        TRACK[usize::from(self.0)].1
+    }
+}
+
+/// A [`Ticker`] whose symbol has been resolved to a [`SymbolId`] up front, so it owns no string.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InternedTicker {
+    pub symbol: SymbolId,
+    pub price: Price,
+    pub volume: f64,
+}
+
+impl Ticker {
+    /// Creates an [`InternedTicker`] with zero volume, or returns `None` if `symbol` isn't known.
+    pub fn with_interned(symbol: &str, price: Price) -> Option<InternedTicker> {
+        Some(InternedTicker {
+            symbol: SymbolId::new(symbol)?,
+            price,
+            volume: 0.0,
+        })
+    }
+}
+
+impl From<InternedTicker> for Ticker {
+    fn from(ticker: InternedTicker) -> Self {
+        Ticker::new(ticker.symbol.as_str(), ticker.price).with_volume(ticker.volume)
+    }
+}
+
+/// Groups interned tickers by network and returns a [`NetworkAggregate`] per network.
+///
+/// Since every [`SymbolId`] is known, this can't fail.
+pub fn aggregate_interned(
+    tickers: impl IntoIterator<Item = InternedTicker>,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    let sums = tickers.into_iter().fold(HashMap::new(), |mut sums, val| {
+        add_to_sums(&mut sums, val.symbol.network(), val.price);
+        sums
+    });
+
+    averages(sums)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aggregate_by_network_ref;
+
+    #[test]
+    fn symbol_id_round_trip() {
+        for (symbol, name) in TRACK {
+            let id = SymbolId::new(symbol).unwrap();
+
+            assert_eq!(*symbol, id.as_str());
+            assert_eq!(*name, id.network());
+        }
+        assert_eq!(None, SymbolId::new("BTC"));
+    }
+
+    #[test]
+    fn aggregate_interned_matches_strings() {
+        let prices = [("S1", 0.1), ("S2", 0.2), ("S1", 0.3), ("s3", 0.4)];
+        let tickers: Vec<_> = prices
+            .iter()
+            .map(|(symbol, price)| Ticker::new(*symbol, *price))
+            .collect();
+        let interned: Vec<_> = prices
+            .iter()
+            .map(|(symbol, price)| Ticker::with_interned(symbol, *price).unwrap())
+            .collect();
+
+        assert_eq!(
+            aggregate_by_network_ref(&tickers),
+            aggregate_interned(interned.iter().copied())
+        );
+        assert_eq!(tickers[0], Ticker::from(interned[0]));
+    }
+
+    #[test]
+    fn aggregate_interned_empty() {
+        assert!(aggregate_interned(vec![]).is_empty());
+    }
+}
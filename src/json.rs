@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Read;
+
+use serde::de::{self, Deserializer as _, SeqAccess, Visitor};
+
+use crate::{
+    averages, lookup_symbol, try_add_to_sums, AggregateError, HashMap, NetworkAggregate,
+    NetworkName, PartialAggregate, Price, PriceValidation, Ticker,
+};
+
+// a `NetworkAggregate` with named fields, as it appears in the JSON
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    count: u32,
+    avg: Price,
+}
+
+/// Serializes an aggregation result to a JSON object keyed by the canonical network symbols, e.g.
+/// `{"S1":{"count":3,"avg":0.2}}`.
+///
+/// The keys are written in [`NetworkName`] order, so equal results give equal strings.
+pub fn result_to_json(result: &HashMap<NetworkName, NetworkAggregate>) -> String {
+    let entries: BTreeMap<_, _> = result
+        .iter()
+        .map(|(name, (count, avg))| {
+            let entry = Entry {
+                count: *count,
+                avg: *avg,
+            };
+            (*name, entry)
+        })
+        .collect();
+    serde_json::to_string(&entries).expect("serializing the entries can't fail")
+}
+
+/// Parses a result written by [`result_to_json`].
+pub fn result_from_json(
+    json: &str,
+) -> Result<HashMap<NetworkName, NetworkAggregate>, serde_json::Error> {
+    let entries: HashMap<NetworkName, Entry> = serde_json::from_str(json)?;
+    let result = entries
+        .into_iter()
+        .map(|(name, entry)| (name, (entry.count, entry.avg)))
+        .collect();
+    Ok(result)
+}
+
+/// Aggregates a JSON array of tickers read from `reader`, folding them one at a time as they're
+/// parsed, so memory doesn't grow with the length of the array.
+///
+/// Tickers are validated like in [`try_aggregate_by_network`](crate::try_aggregate_by_network).
+/// Errors about a ticker carry its 0-based index in the array.
+pub fn aggregate_json_reader<R: Read>(
+    reader: R,
+) -> Result<HashMap<NetworkName, NetworkAggregate>, JsonError> {
+    let mut fold = TickerFold::default();
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let parsed = deserializer
+        .deserialize_seq(&mut fold)
+        .and_then(|()| deserializer.end());
+
+    if let Some((index, source)) = fold.rejected {
+        return Err(JsonError::InvalidTicker { index, source });
+    }
+    match (parsed, fold.current) {
+        (Ok(()), _) => Ok(averages(fold.sums)),
+        (Err(source), Some(index)) => Err(JsonError::MalformedTicker { index, source }),
+        (Err(err), None) => Err(JsonError::Json(err)),
+    }
+}
+
+// folds the array elements into `sums` as the deserializer visits them
+#[derive(Default)]
+struct TickerFold {
+    sums: HashMap<NetworkName, PartialAggregate>,
+    // the index of the element being parsed, if any
+    current: Option<usize>,
+    rejected: Option<(usize, AggregateError)>,
+}
+
+impl<'de> Visitor<'de> for &mut TickerFold {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an array of tickers")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        for index in 0.. {
+            self.current = Some(index);
+            let Some(ticker) = seq.next_element::<Ticker>()? else {
+                break;
+            };
+            let validated = try_add_to_sums(
+                &mut self.sums,
+                ticker,
+                PriceValidation::default(),
+                lookup_symbol,
+            );
+            if let Err(err) = validated {
+                self.rejected = Some((index, err));
+                return Err(de::Error::custom("invalid ticker"));
+            }
+        }
+        self.current = None;
+        Ok(())
+    }
+}
+
+/// The error returned by [`aggregate_json_reader`].
+#[derive(Debug)]
+pub enum JsonError {
+    /// The input isn't a JSON array, or is followed by something other than whitespace.
+    Json(serde_json::Error),
+    /// The element at `index` isn't a valid ticker object.
+    MalformedTicker {
+        index: usize,
+        source: serde_json::Error,
+    },
+    /// The ticker at `index` was rejected by validation.
+    InvalidTicker {
+        index: usize,
+        source: AggregateError,
+    },
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "invalid ticker array: {err}"),
+            Self::MalformedTicker { index, source } => {
+                write!(f, "ticker {index}: malformed: {source}")
+            }
+            Self::InvalidTicker { index, source } => write!(f, "ticker {index}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Json(err) | Self::MalformedTicker { source: err, .. } => Some(err),
+            Self::InvalidTicker { source, .. } => Some(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn result_to_json_uses_symbols() {
+        let result = HashMap::from([(NetworkName::N3, (1, 0.5)), (NetworkName::N1, (3, 0.2))]);
+
+        assert_eq!(
+            r#"{"S1":{"count":3,"avg":0.2},"s3":{"count":1,"avg":0.5}}"#,
+            result_to_json(&result)
+        );
+    }
+
+    #[test]
+    fn result_from_json_round_trip() {
+        let result = HashMap::from([(NetworkName::N1, (3, 0.2)), (NetworkName::N2, (1, 0.4))]);
+
+        assert_eq!(result, result_from_json(&result_to_json(&result)).unwrap());
+        assert!(result_from_json(r#"{"N1":{"count":1,"avg":0.1}}"#).is_err());
+    }
+
+    #[test]
+    fn aggregate_json_reader_folds_array() {
+        let json = r#"[
+            {"symbol": "S1", "price": 0.1},
+            {"symbol": "S2", "price": 0.2},
+            {"symbol": "S1", "price": 0.3}
+        ]"#;
+        let result = aggregate_json_reader(json.as_bytes()).unwrap();
+
+        assert_eq!(2, result[&NetworkName::N1].0);
+        assert_eq!((1, 0.2), result[&NetworkName::N2]);
+        assert!(aggregate_json_reader("[]".as_bytes()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn aggregate_json_reader_errors_carry_index() {
+        let malformed = r#"[{"symbol": "S1", "price": 0.1}, {"symbol": "S1"}]"#;
+        assert!(matches!(
+            aggregate_json_reader(malformed.as_bytes()),
+            Err(JsonError::MalformedTicker { index: 1, .. })
+        ));
+
+        let unknown = r#"[{"symbol": "BTC", "price": 0.1}]"#;
+        assert_eq!(
+            "ticker 0: unknown symbol `BTC`",
+            aggregate_json_reader(unknown.as_bytes())
+                .unwrap_err()
+                .to_string()
+        );
+
+        assert!(matches!(
+            aggregate_json_reader(r#"{"symbol": "S1"}"#.as_bytes()),
+            Err(JsonError::Json(_))
+        ));
+    }
+}
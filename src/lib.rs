@@ -1,140 +1,4581 @@
-use std::collections::HashMap;
+//! Aggregates ticker prices by the network their symbols belong to.
+//!
+//! Every aggregation returns an empty result for empty input, and leaves out networks without
+//! tickers rather than reporting them with a `NaN` average. [`averages_filtered`] and
+//! [`aggregate_all_networks`] are the exception: they report every network, with no average for
+//! the empty ones.
+//!
+//! Ordered results, like [`aggregate_sorted`], [`aggregate_btreemap`], the rankings of
+//! [`top_networks_by_count`] and [`top_networks_by_value`] and the network lists of
+//! [`AverageDiff`] and [`AggregateComparison`], break every tie by [`NetworkName`] ascending, so
+//! their order never depends on hashing or on the order of the input.
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-enum NetworkName {
-    N1,
-    N2,
-    N3,
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// widening a `Price` with `f64::from` is a no-op with `f64-prices`
+#![cfg_attr(feature = "f64-prices", allow(clippy::useless_conversion))]
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+compile_error!("either the `std` or the `alloc` feature must be enabled");
+
+extern crate alloc;
+
+mod aggregator;
+mod cents;
+mod classifier;
+#[cfg(target_has_atomic = "64")]
+mod concurrent;
+#[cfg(feature = "csv")]
+mod csv;
+mod currency;
+#[cfg(feature = "decimal")]
+mod decimal;
+mod interned;
+#[cfg(feature = "json")]
+mod json;
+#[macro_use]
+mod macros;
+#[doc(hidden)]
+pub use macros::{__is_strictly_sorted, __str_lt};
+mod math;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod partial;
+#[cfg(feature = "std")]
+mod shared_cache;
+#[cfg(feature = "tdigest")]
+mod sketch;
+#[cfg(feature = "json")]
+mod snapshot;
+#[cfg(feature = "async")]
+mod stream;
+mod streaming;
+mod window;
+
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ops::Deref;
+use core::time::Duration;
+
+// without `std` the maps come from `hashbrown`, which has the same API
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "json")]
+pub use aggregator::MappingError;
+pub use aggregator::{Aggregator, AggregatorBuilder, AggregatorSet, BuildError};
+pub use cents::{aggregate_cents_by_network, CentsAggregate, CentsTicker};
+pub use classifier::{
+    aggregate_with_classifier, aggregate_with_normalizer, DefaultClassifier, PrefixClassifier,
+    SymbolClassifier,
+};
+#[cfg(target_has_atomic = "64")]
+pub use concurrent::ConcurrentAggregator;
+#[cfg(feature = "csv")]
+pub use csv::{from_csv_reader, CsvError};
+pub use currency::{aggregate_normalized, Currency};
+#[cfg(feature = "decimal")]
+pub use decimal::{aggregate_decimal_by_network, DecimalAggregate, DecimalTicker};
+pub use interned::{aggregate_interned, InternedTicker, SymbolId};
+#[cfg(feature = "json")]
+pub use json::{aggregate_json_reader, result_from_json, result_to_json, JsonError};
+#[cfg(feature = "rayon")]
+pub use parallel::par_aggregate_by_network;
+pub use partial::{
+    compare, diff_averages, finalize_aggregates, merge_aggregates, merge_partial_aggregates,
+    partial_aggregate_by_network, AggregateComparison, AverageDiff, PartialAggregate,
+};
+#[cfg(feature = "std")]
+pub use shared_cache::{shared_name_from_symbol, try_shared_name_from_symbol, SharedCache};
+#[cfg(feature = "tdigest")]
+pub use sketch::{
+    approx_percentile_by_network, sketch_by_network, PriceSketch, TDIGEST_COMPRESSION,
+};
+#[cfg(feature = "json")]
+pub use snapshot::{Snapshot, SnapshotError, SNAPSHOT_VERSION};
+#[cfg(feature = "async")]
+pub use stream::aggregate_stream;
+pub use streaming::StreamingAggregator;
+pub use window::WindowedAggregator;
+
+// the single source of truth for the networks and `SYMBOL_TABLE`
+builtin_networks! {
+    /// A network a ticker symbol belongs to.
+    ///
+    /// Networks are ordered by declaration, `N1 < N2 < N3`. The order is part of the public API,
+    /// so sorted output and tie-breaking based on it are stable across releases.
+    pub enum NetworkName {
+        N1 => "S1",
+        N2 => "S2" | "WETH",
+        N3 => "s3",
+    }
+}
+
+/// The group a ticker is aggregated into by [`aggregate_lenient`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NetworkBucket {
+    /// The ticker's symbol belongs to this network.
+    Known(NetworkName),
+    /// The ticker's symbol doesn't belong to any network.
+    Unknown,
+}
+
+impl From<NetworkName> for NetworkBucket {
+    fn from(name: NetworkName) -> Self {
+        Self::Known(name)
+    }
+}
+
+// sorted by symbol, so that `track_entry` can binary search it. `networks!` already rejects
+// duplicate symbols, which `track_entry` would otherwise resolve to an arbitrary one of them
+const TRACK: &[(&str, NetworkName)] = NetworkName::SORTED_SYMBOLS;
+
+// chain id and display name of every network, kept in the order of the variants
+const METADATA: &[(NetworkName, u32, &str)] = &[
+    (NetworkName::N1, 1, "Mainnet"),
+    (NetworkName::N2, 10, "Optimism"),
+    (NetworkName::N3, 137, "Polygon"),
+];
+
+// the symbols of `TRACK` packed into big-endian `u64`s, zero padded or truncated to 8 bytes, and
+// their lengths. For symbols of up to 8 bytes the pairs compare like the strings, so they can be
+// binary searched with integer comparisons instead of `str` ones
+#[cfg(not(feature = "phf"))]
+const PACKED_TRACK: [(u64, usize); TRACK.len()] = {
+    let mut packed = [(0, 0); TRACK.len()];
+    let mut i = 0;
+    while i < TRACK.len() {
+        packed[i] = (pack_symbol(TRACK[i].0.as_bytes()), TRACK[i].0.len());
+        i += 1;
+    }
+    packed
+};
+
+// up to this many symbols `track_entry` scans `TRACK` instead of searching it
+#[cfg(not(feature = "phf"))]
+const SCANNED_TRACK_LEN: usize = 8;
+
+// `track_entry` packs with shifts instead, which are much faster than this loop at runtime
+#[cfg(not(feature = "phf"))]
+const fn pack_symbol(symbol: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    let mut i = 0;
+    while i < symbol.len() && i < bytes.len() {
+        bytes[i] = symbol[i];
+        i += 1;
+    }
+    u64::from_be_bytes(bytes)
+}
+
+fn track_entry(symbol: &str) -> Option<(&'static str, NetworkName)> {
+    #[cfg(feature = "phf")]
+    return SYMBOL_TABLE
+        .get_entry(symbol)
+        .map(|(sym, name)| (*sym, *name));
+
+    #[cfg(not(feature = "phf"))]
+    {
+        // a handful of symbols is scanned faster than it's searched or hashed, see the
+        // `symbol_resolution` bench. The condition is a constant, so only one branch is compiled
+        if TRACK.len() <= SCANNED_TRACK_LEN {
+            return TRACK.iter().find(|(sym, _)| *sym == symbol).copied();
+        }
+
+        let index = if symbol.len() <= 8 {
+            let packed = symbol
+                .bytes()
+                .fold(0, |packed, byte| packed << 8 | u64::from(byte));
+            // `checked_shl` covers the empty symbol, which would shift by 64
+            let packed = packed
+                .checked_shl(64 - 8 * symbol.len() as u32)
+                .unwrap_or(0);
+            let key = (packed, symbol.len());
+            PACKED_TRACK.binary_search(&key)
+        } else {
+            TRACK.binary_search_by(|(sym, _)| (*sym).cmp(symbol))
+        };
+        index.ok().map(|i| TRACK[i])
+    }
+}
+
+// resolution for the panicking aggregations
+fn expect_network(symbol: &str) -> NetworkName {
+    lookup_symbol(symbol).expect("symbol is valid")
+}
+
+/// Resolves `symbol` to its network with a binary search of the symbol table, or a perfect hash
+/// lookup with the `phf` feature.
+///
+/// Returns `None` if `symbol` isn't known. Unlike [`try_name_from_symbol`] this needs no cache.
+pub fn lookup_symbol(symbol: &str) -> Option<NetworkName> {
+    track_entry(symbol).map(|(_, name)| name)
+}
+
+/// Iterates over every known symbol and its network, in symbol order.
+pub fn known_symbols() -> impl Iterator<Item = (&'static str, NetworkName)> {
+    TRACK.iter().copied()
+}
+
+/// Returns every symbol that maps to `network` in declaration order, so its canonical
+/// [`symbol`](NetworkName::symbol) comes first and its aliases follow.
+pub fn symbols_for(network: NetworkName) -> Vec<&'static str> {
+    NetworkName::SYMBOLS
+        .iter()
+        .filter(|(_, name)| *name == network)
+        .map(|(sym, _)| *sym)
+        .collect()
+}
+
+/// Returns the aliases of `network`, that is [`symbols_for`] without its canonical
+/// [`symbol`](NetworkName::symbol).
+pub fn aliases_for(network: NetworkName) -> Vec<&'static str> {
+    let mut symbols = symbols_for(network);
+    symbols.remove(0);
+    symbols
+}
+
+impl NetworkName {
+    /// Returns the numeric chain id of the network.
+    pub fn chain_id(self) -> u32 {
+        self.metadata().1
+    }
+
+    /// Returns the human-readable name of the network.
+    pub fn display_name(self) -> &'static str {
+        self.metadata().2
+    }
+
+    /// Returns the network with the chain id `id`, or `None` if there's none.
+    pub fn from_chain_id(id: u32) -> Option<Self> {
+        METADATA
+            .iter()
+            .find(|(_, chain_id, _)| *chain_id == id)
+            .map(|(name, _, _)| *name)
+    }
+
+    fn metadata(self) -> (NetworkName, u32, &'static str) {
+        *METADATA
+            .iter()
+            .find(|(name, _, _)| *name == self)
+            .expect("every network has metadata")
+    }
+}
+
+// networks are (de)serialized as their symbols rather than variant names, so that consumers only
+// ever see the market symbols
+#[cfg(feature = "serde")]
+impl serde::Serialize for NetworkName {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.symbol())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NetworkName {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let symbol = Cow::<str>::deserialize(deserializer)?;
+        symbol.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The error returned when parsing a [`NetworkName`] from an unknown symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseNetworkNameError {
+    /// The symbol that failed to parse.
+    pub symbol: String,
+    /// The known symbol closest to `symbol`, if one is only a typo or two away.
+    pub did_you_mean: Option<&'static str>,
+}
+
+impl ParseNetworkNameError {
+    // suggestions further away than this are more likely to confuse than to help
+    const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+    /// Creates the error for `symbol`, suggesting the first of the `known` symbols with the
+    /// smallest Levenshtein distance to it, if that's at most 2 and the two symbols have at least
+    /// one character in common.
+    pub fn new(symbol: &str, known: impl IntoIterator<Item = &'static str>) -> Self {
+        let len = symbol.chars().count();
+        let did_you_mean = known
+            .into_iter()
+            .map(|candidate| (levenshtein(symbol, candidate), candidate))
+            // a distance of the longer length means every character was replaced
+            .filter(|(distance, candidate)| {
+                *distance <= Self::MAX_SUGGESTION_DISTANCE
+                    && *distance < len.max(candidate.chars().count())
+            })
+            // `min_by_key` keeps the first of equally close candidates
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate);
+        Self {
+            symbol: String::from(symbol),
+            did_you_mean,
+        }
+    }
+}
+
+impl fmt::Display for ParseNetworkNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown network symbol `{}`", self.symbol)?;
+        if let Some(suggestion) = self.did_you_mean {
+            write!(f, ", did you mean `{suggestion}`?")?;
+        }
+        Ok(())
+    }
+}
+
+// the number of single character insertions, deletions and substitutions that turn `a` into `b`
+fn levenshtein(a: &str, b: &str) -> usize {
+    // one row of the distance matrix, `row[j]` being the distance between the prefix of `a` and
+    // the first `j` characters of `b`
+    let mut row: Vec<usize> = (0..=b.chars().count()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.chars().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[row.len() - 1]
+}
+
+impl core::error::Error for ParseNetworkNameError {}
+
+/// The type of ticker prices and the averages computed from them.
+///
+/// This is `f32` unless the `f64-prices` feature is enabled. Sums are accumulated in `f64` either
+/// way.
+#[cfg(not(feature = "f64-prices"))]
+pub type Price = f32;
+/// The type of ticker prices and the averages computed from them.
+///
+/// This is `f64` because the `f64-prices` feature is enabled.
+#[cfg(feature = "f64-prices")]
+pub type Price = f64;
+
+/// Per-network aggregation result: `.0` is the number of tickers, `.1` is their average price.
+///
+/// [`NetworkStats`] carries the same data with named fields.
+pub type NetworkAggregate = (u32, Price);
+
+/// Groups `tickers` by network and returns a [`NetworkAggregate`] per network.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known. Use [`try_aggregate_by_network`] for untrusted input.
+#[deprecated(note = "use `aggregate_stats_by_network`, which returns named fields")]
+pub fn aggregate_by_network(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("aggregate_by_network").entered();
+
+    aggregate_by(tickers, expect_network)
+}
+
+/// Like [`aggregate_by_network`], but returns the networks sorted by [`NetworkName`], so the
+/// output order is reproducible.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_sorted(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> Vec<(NetworkName, NetworkAggregate)> {
+    let mut result: Vec<_> = aggregate_by(tickers, expect_network).into_iter().collect();
+    result.sort_unstable_by_key(|(name, _)| *name);
+    result
+}
+
+/// Like [`aggregate_by_network`], but returns a [`BTreeMap`], which iterates in [`NetworkName`]
+/// order.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_btreemap(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> BTreeMap<NetworkName, NetworkAggregate> {
+    aggregate_by(tickers, expect_network).into_iter().collect()
+}
+
+/// Like [`aggregate_by_network`], but calls `on_ticker` with every ticker and its network after
+/// the ticker is classified and before it's folded.
+///
+/// The callback only gets shared access to the ticker, so it can't change the result.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_with_callback<F: FnMut(&Ticker, NetworkName)>(
+    tickers: impl IntoIterator<Item = Ticker>,
+    mut on_ticker: F,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    let sums = tickers.into_iter().fold(HashMap::new(), |mut sums, val| {
+        let name = expect_network(&val.symbol);
+        on_ticker(&val, name);
+        add_to_sums(&mut sums, name, val.price);
+        sums
+    });
+
+    averages(sums)
+}
+
+/// Like [`aggregate_by_network`], but only folds the tickers for which `keep` returns `true`.
+///
+/// The predicate runs before the symbol is resolved, so rejected tickers don't affect the result
+/// in any way.
+///
+/// # Panics
+///
+/// Panics if a kept ticker's symbol isn't known.
+pub fn aggregate_filtered(
+    tickers: impl IntoIterator<Item = Ticker>,
+    keep: impl Fn(&Ticker) -> bool,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    aggregate_by(tickers.into_iter().filter(keep), expect_network)
+}
+
+/// Like [`aggregate_by_network`], but only folds the tickers of the given `kind`, so trades and
+/// quotes aren't averaged together.
+///
+/// To aggregate both kinds separately in one pass, group by network and kind with
+/// [`aggregate_grouped`].
+///
+/// # Panics
+///
+/// Panics if the symbol of a ticker of that kind isn't known.
+pub fn aggregate_by_network_for(
+    kind: TickerKind,
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    aggregate_filtered(tickers, |ticker| ticker.kind == kind)
+}
+
+/// Returns the `n` networks with the most tickers, busiest first.
+///
+/// Networks with equal counts are ordered by [`NetworkName`]. If fewer than `n` networks occur,
+/// all of them are returned.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn top_networks_by_count(
+    tickers: impl IntoIterator<Item = Ticker>,
+    n: usize,
+) -> Vec<(NetworkName, u32)> {
+    let mut counts: Vec<_> = aggregate_by(tickers, expect_network)
+        .into_iter()
+        .map(|(name, (count, _))| (name, count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts.truncate(n);
+    counts
+}
+
+/// Returns the `n` networks with the highest total traded value, `sum(price * volume)`, highest
+/// first.
+///
+/// Networks whose tickers add up to zero value are left out. Networks with equal values are
+/// ordered by [`NetworkName`], and values are compared with [`f64::total_cmp`].
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn top_networks_by_value(tickers: &[Ticker], n: usize) -> Vec<(NetworkName, f64)> {
+    let mut values: Vec<_> = tickers
+        .iter()
+        .fold(HashMap::<NetworkName, f64>::new(), |mut values, val| {
+            *values.entry(expect_network(&val.symbol)).or_default() +=
+                f64::from(val.price) * val.volume;
+            values
+        })
+        .into_iter()
+        .filter(|(_, value)| *value != 0.0)
+        .collect();
+    values.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    values.truncate(n);
+    values
+}
+
+/// Groups `tickers` by network and returns a [`NetworkAggregate`] per bucket, but never fails:
+/// tickers with unknown symbols are aggregated in [`NetworkBucket::Unknown`].
+///
+/// The counts of all buckets add up to the number of tickers. Use [`try_aggregate_by_network`] to
+/// reject unknown symbols instead.
+pub fn aggregate_lenient(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkBucket, NetworkAggregate> {
+    aggregate_by(tickers, |symbol| {
+        lookup_symbol(symbol).map_or(NetworkBucket::Unknown, NetworkBucket::Known)
+    })
+}
+
+/// Like [`aggregate_lenient`], but skips tickers with unknown symbols and counts how often each
+/// unknown symbol occurred instead.
+///
+/// An empty second map means every ticker was classified.
+pub fn aggregate_lenient_with_report(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> (HashMap<NetworkName, NetworkAggregate>, HashMap<String, u32>) {
+    let mut sums = HashMap::new();
+    let mut unknown = HashMap::<String, u32>::new();
+    for val in tickers {
+        match lookup_symbol(&val.symbol) {
+            Some(name) => add_to_sums(&mut sums, name, val.price),
+            None => *unknown.entry(val.symbol.into_owned()).or_default() += 1,
+        }
+    }
+
+    (averages(sums), unknown)
+}
+
+/// Groups `tickers` by the key `classify` returns for their symbols and returns the ticker count
+/// and the average price per key.
+pub fn aggregate_by<K: Eq + Hash>(
+    tickers: impl IntoIterator<Item = Ticker>,
+    classify: impl FnMut(&str) -> K,
+) -> HashMap<K, NetworkAggregate> {
+    aggregate_by_with_hasher(tickers, classify)
+}
+
+/// Same as [`aggregate_by`], but every map built along the way, including the returned one, uses
+/// the hasher `S`.
+///
+/// A fixed hasher, such as `BuildHasherDefault<FxHasher>`, makes the map layout and iteration order
+/// the same from run to run.
+pub fn aggregate_by_with_hasher<K: Eq + Hash, S: BuildHasher + Default>(
+    tickers: impl IntoIterator<Item = Ticker>,
+    mut classify: impl FnMut(&str) -> K,
+) -> HashMap<K, NetworkAggregate, S> {
+    let sums = tickers
+        .into_iter()
+        .fold(HashMap::<_, _, S>::default(), |mut sums, val| {
+            add_to_sums(&mut sums, classify(&val.symbol), val.price);
+            sums
+        });
+
+    averages(sums)
+}
+
+/// Same as [`aggregate_by_with_hasher`], grouping by network.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_by_network_with_hasher<S: BuildHasher + Default>(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, NetworkAggregate, S> {
+    aggregate_by_with_hasher(tickers, expect_network)
+}
+
+/// Groups `tickers` by network and returns a [`NetworkAggregate`] per network.
+///
+/// Fails on the first ticker whose symbol isn't known or whose price isn't finite, with an
+/// [`AggregateError::InvalidTicker`] carrying its index in `tickers`. Negative prices are
+/// accepted, see [`try_aggregate_by_network_with`] to reject them.
+pub fn try_aggregate_by_network(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> Result<HashMap<NetworkName, NetworkAggregate>, AggregateError> {
+    try_aggregate_by_network_with(tickers, PriceValidation::default())
+}
+
+/// Checks applied to ticker prices by [`try_aggregate_by_network_with`]. Non-finite prices are
+/// always rejected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PriceValidation {
+    /// What happens to negative prices. They're allowed by default, as some instruments
+    /// legitimately trade below zero.
+    pub negative_prices: NegativePricePolicy,
+    /// What happens to a ticker that would overflow its network's count, see [`OverflowPolicy`].
+    pub count_overflow: OverflowPolicy,
+}
+
+/// How [`PriceValidation`] treats a ticker that would push its network's count past `u32::MAX`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The ticker fails the aggregation with [`AggregateError::CountOverflow`].
+    #[default]
+    Checked,
+    /// The count stays at `u32::MAX` and the ticker is left out, so the average stays that of
+    /// the tickers counted so far.
+    Saturating,
+    /// The count wraps around to 0 and the price is still added to the sum. The average of that
+    /// network is meaningless from then on, this is only for callers that rely on an unchecked
+    /// count.
+    Wrapping,
+}
+
+/// How [`PriceValidation`] treats negative prices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NegativePricePolicy {
+    /// Negative prices are aggregated like any other.
+    #[default]
+    Allow,
+    /// A negative price fails the aggregation with [`AggregateError::InvalidPrice`].
+    Reject,
+    /// Tickers with negative prices are left out, as if they weren't in the input.
+    Skip,
+}
+
+impl PriceValidation {
+    // whether `price` passes, possibly to be skipped
+    fn accepts(self, price: Price) -> bool {
+        price.is_finite() && (self.negative_prices != NegativePricePolicy::Reject || price >= 0.0)
+    }
+
+    fn skips(self, price: Price) -> bool {
+        self.negative_prices == NegativePricePolicy::Skip && price < 0.0
+    }
+}
+
+/// Like [`try_aggregate_by_network`], but validates prices according to `validation`.
+pub fn try_aggregate_by_network_with(
+    tickers: impl IntoIterator<Item = Ticker>,
+    validation: PriceValidation,
+) -> Result<HashMap<NetworkName, NetworkAggregate>, AggregateError> {
+    try_sums(tickers, validation, lookup_symbol).map(averages)
+}
+
+/// Like [`try_aggregate_by_network`], but resolves symbols through `cache`, so a cache created
+/// [`with_stats`](SymbolCache::with_stats) counts how the lookups of the aggregation went.
+///
+/// The same cache can be passed to several aggregations, and its counts add up across them.
+pub fn try_aggregate_by_network_cached(
+    tickers: impl IntoIterator<Item = Ticker>,
+    cache: &mut SymbolCache,
+) -> Result<HashMap<NetworkName, NetworkAggregate>, AggregateError> {
+    try_sums(tickers, PriceValidation::default(), |symbol| {
+        cache.resolve(symbol)
+    })
+    .map(averages)
+}
+
+/// Like [`try_aggregate_by_network`], but doesn't stop at the first rejected ticker: every ticker
+/// that passes is aggregated, and every rejected one is reported with its index in `tickers`, in
+/// input order.
+///
+/// The errors are the reasons [`AggregateError::InvalidTicker`] would carry. An empty list means
+/// the result is the same as that of `try_aggregate_by_network`.
+pub fn aggregate_collecting_errors(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> (
+    HashMap<NetworkName, NetworkAggregate>,
+    Vec<(usize, AggregateError)>,
+) {
+    let mut sums = HashMap::with_capacity(METADATA.len());
+    let mut errors = Vec::new();
+    for (index, val) in tickers.into_iter().enumerate() {
+        if let Err(err) = try_add_to_sums(&mut sums, val, PriceValidation::default(), lookup_symbol)
+        {
+            errors.push((index, err));
+        }
+    }
+
+    (averages(sums), errors)
+}
+
+/// Like [`aggregate_by_network`], but only borrows the tickers, so the same slice can be
+/// aggregated several times.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_by_network_ref(tickers: &[Ticker]) -> HashMap<NetworkName, NetworkAggregate> {
+    aggregate_grouped(tickers, |ticker| expect_network(&ticker.symbol))
+}
+
+/// Returns the sum of the prices per network, before it's divided into an average.
+///
+/// The sums are accumulated in `f64` with the same compensation as the averages, see
+/// [`PartialAggregate::sum`]. To get the sums and the averages from one pass, use
+/// [`partial_aggregate_by_network`] instead.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn sum_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, f64> {
+    tickers
+        .iter()
+        .fold(HashMap::<_, PartialAggregate>::new(), |mut sums, val| {
+            add_to_sums(&mut sums, expect_network(&val.symbol), val.price);
+            sums
+        })
+        .into_iter()
+        .map(|(name, partial)| (name, partial.sum()))
+        .collect()
+}
+
+/// Groups `tickers` by the key `key_of` returns for them and returns the ticker count and the
+/// average price per key.
+///
+/// Unlike [`aggregate_by`], the key can depend on the whole ticker, e.g. on a price band.
+pub fn aggregate_grouped<K: Eq + Hash, F: Fn(&Ticker) -> K>(
+    tickers: &[Ticker],
+    key_of: F,
+) -> HashMap<K, NetworkAggregate> {
+    let sums = tickers.iter().fold(HashMap::new(), |mut sums, val| {
+        add_to_sums(&mut sums, key_of(val), val.price);
+        sums
+    });
+
+    averages(sums)
+}
+
+/// Like [`aggregate_by_network_ref`], but over borrowed [`TickerRef`]s, so no symbol needs to be
+/// owned.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_ticker_refs(tickers: &[TickerRef<'_>]) -> HashMap<NetworkName, NetworkAggregate> {
+    let sums = tickers.iter().fold(HashMap::new(), |mut sums, val| {
+        add_to_sums(&mut sums, expect_network(val.symbol), val.price);
+        sums
+    });
+
+    averages(sums)
+}
+
+/// Like [`aggregate_by_network_ref`], but skips every ticker that is identical to an earlier one,
+/// e.g. a tick a feed replayed.
+///
+/// Tickers are identical if their symbols, timestamps and currencies are equal and their prices,
+/// volumes and quotes have the same bits. Bit equality is exact: prices that differ by rounding
+/// noise both count, `0.0` and `-0.0` are different, and a `NaN` equals a `NaN` with the same
+/// payload.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_dedup(tickers: &[Ticker]) -> HashMap<NetworkName, NetworkAggregate> {
+    let mut seen = HashSet::new();
+    let sums = tickers
+        .iter()
+        .filter(|val| {
+            seen.insert((
+                &*val.symbol,
+                val.price.to_bits(),
+                val.volume.to_bits(),
+                val.quote
+                    .map(|quote| (quote.bid.to_bits(), quote.ask.to_bits())),
+                val.timestamp,
+                val.currency.as_ref(),
+                val.kind,
+            ))
+        })
+        .fold(HashMap::new(), |mut sums, val| {
+            add_to_sums(&mut sums, expect_network(&val.symbol), val.price);
+            sums
+        });
+
+    averages(sums)
+}
+
+/// Like [`aggregate_by_network_ref`], but folds `tickers` in chunks of `chunk_size` and calls
+/// `progress` with the number of tickers processed so far after each chunk.
+///
+/// If `progress` returns `false` the remaining chunks are skipped, and the result covers only the
+/// tickers processed until then.
+///
+/// # Panics
+///
+/// Panics if `chunk_size` is zero or a ticker's symbol isn't known.
+pub fn aggregate_chunked(
+    tickers: &[Ticker],
+    chunk_size: usize,
+    mut progress: impl FnMut(usize) -> bool,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    let mut sums = HashMap::new();
+    let mut processed = 0;
+    for chunk in tickers.chunks(chunk_size) {
+        for val in chunk {
+            add_to_sums(&mut sums, expect_network(&val.symbol), val.price);
+        }
+        processed += chunk.len();
+        if !progress(processed) {
+            break;
+        }
+    }
+
+    averages(sums)
+}
+
+/// Aggregates `tickers` until every network in `expected` has at least one ticker, and returns the
+/// result so far and whether that happened before `tickers` ran out.
+///
+/// No tickers are taken from `tickers` after the last expected network was seen, so passing
+/// `iter.by_ref()` leaves the rest in `iter`. With an empty `expected` nothing is taken at all.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_until_all_seen(
+    tickers: impl IntoIterator<Item = Ticker>,
+    expected: &[NetworkName],
+) -> (HashMap<NetworkName, NetworkAggregate>, bool) {
+    // indexed by variant, which is also the order of `METADATA`
+    let mut missing = [false; METADATA.len()];
+    for name in expected {
+        missing[*name as usize] = true;
+    }
+    let mut remaining = missing.iter().filter(|missing| **missing).count();
+
+    let mut sums = HashMap::new();
+    let mut tickers = tickers.into_iter();
+    while remaining > 0 {
+        let Some(val) = tickers.next() else {
+            break;
+        };
+        let name = expect_network(&val.symbol);
+        add_to_sums(&mut sums, name, val.price);
+        if core::mem::take(&mut missing[name as usize]) {
+            remaining -= 1;
+        }
+    }
+
+    (averages(sums), remaining == 0)
+}
+
+/// Aggregates several batches of tickers, e.g. from separate feeds, as if they were one.
+///
+/// The batches are folded one after another into the same sums, so they are never concatenated
+/// and the averages match aggregating the flattened input.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_all(
+    batches: impl IntoIterator<Item = Vec<Ticker>>,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    aggregate_by(batches.into_iter().flatten(), expect_network)
+}
+
+/// Like [`aggregate_all`] for a single batch, but also returns the average price of all tickers,
+/// regardless of their network.
+///
+/// The overall average is computed by merging the per-network sums, so the tickers are only folded
+/// once. It's `None` if there are no tickers.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known, or if the total count would overflow.
+pub fn aggregate_with_overall(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> (HashMap<NetworkName, NetworkAggregate>, Option<Price>) {
+    let sums = partial_aggregate_by_network(tickers);
+    let overall = sums
+        .values()
+        .fold(PartialAggregate::new(), |mut overall, partial| {
+            overall.merge(partial);
+            overall
+        });
+    (averages(sums), overall.finalize())
+}
+
+/// Like [`aggregate_by_network_ref`], but writes the result into `out`, reusing its allocation.
+///
+/// `out` is cleared first, so entries from earlier calls never leak into the result. The sums are
+/// kept on the stack, so a call doesn't allocate once `out` has room for every network.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_into(tickers: &[Ticker], out: &mut HashMap<NetworkName, NetworkAggregate>) {
+    out.clear();
+
+    // indexed by variant, which is also the order of `METADATA`
+    let mut sums = [PartialAggregate::new(); METADATA.len()];
+    for val in tickers {
+        sums[expect_network(&val.symbol) as usize].add_price(val.price);
+    }
+
+    for ((name, _, _), partial) in METADATA.iter().zip(sums) {
+        if let Some(aggregate) = partial.to_aggregate() {
+            out.insert(*name, aggregate);
+        }
+    }
+}
+
+fn try_sums(
+    tickers: impl IntoIterator<Item = Ticker>,
+    validation: PriceValidation,
+    mut classify: impl FnMut(&str) -> Option<NetworkName>,
+) -> Result<HashMap<NetworkName, PartialAggregate>, AggregateError> {
+    tickers
+        .into_iter()
+        .enumerate()
+        // there are only a few networks, so room for all of them is reserved up front
+        .try_fold(
+            HashMap::with_capacity(METADATA.len()),
+            |mut sums, (index, val)| {
+                try_add_to_sums(&mut sums, val, validation, &mut classify).map_err(|reason| {
+                    AggregateError::InvalidTicker {
+                        index,
+                        reason: Box::new(reason),
+                    }
+                })?;
+                Ok(sums)
+            },
+        )
+}
+
+/// Validates `ticker` and folds it into `sums`, leaving them untouched if it's rejected.
+fn try_add_to_sums(
+    sums: &mut HashMap<NetworkName, PartialAggregate>,
+    ticker: Ticker,
+    validation: PriceValidation,
+    classify: impl FnOnce(&str) -> Option<NetworkName>,
+) -> Result<(), AggregateError> {
+    let Some(name) = classify(&ticker.symbol) else {
+        return Err(AggregateError::UnknownSymbol(ticker.symbol.into_owned()));
+    };
+    if !validation.accepts(ticker.price) {
+        return Err(AggregateError::InvalidPrice {
+            symbol: ticker.symbol.into_owned(),
+            price: ticker.price,
+        });
+    }
+    if validation.skips(ticker.price) {
+        return Ok(());
+    }
+    let partial = sums.entry(name).or_default();
+    match validation.count_overflow {
+        OverflowPolicy::Checked => {
+            if !partial.checked_add_price(ticker.price) {
+                return Err(AggregateError::CountOverflow(name));
+            }
+        }
+        // a full count leaves the partial aggregate untouched
+        OverflowPolicy::Saturating => _ = partial.checked_add_price(ticker.price),
+        OverflowPolicy::Wrapping => partial.wrapping_add_price(ticker.price),
+    }
+    Ok(())
+}
+
+fn add_to_sums<K: Eq + Hash, S: BuildHasher>(
+    sums: &mut HashMap<K, PartialAggregate, S>,
+    key: K,
+    price: Price,
+) {
+    sums.entry(key).or_default().add_price(price);
+}
+
+fn merge_sums<K: Eq + Hash>(
+    mut sums: HashMap<K, PartialAggregate>,
+    other: HashMap<K, PartialAggregate>,
+) -> HashMap<K, PartialAggregate> {
+    for (key, partial) in other {
+        sums.entry(key).or_default().merge(&partial);
+    }
+    sums
+}
+
+fn averages<K: Eq + Hash, S: BuildHasher + Default>(
+    sums: impl IntoIterator<Item = (K, PartialAggregate)>,
+) -> HashMap<K, NetworkAggregate, S> {
+    sums.into_iter()
+        .filter_map(|(key, partial)| Some((key, partial.to_aggregate()?)))
+        .collect()
+}
+
+/// Computes the average price per network, rounded to `decimals` decimal places with ties going
+/// to the even digit, e.g. `0.125` becomes `0.12` with two decimals.
+///
+/// The average is rounded in `f64` and only then narrowed to [`Price`], so the result is the
+/// `Price` closest to the rounded decimal. Most decimals have no exact binary representation, and
+/// an `f32` holds only about 7 significant digits, so more decimals than that won't show up in the
+/// result. Once `decimals` exceeds what the `f64` average can hold, it's returned as is.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_rounded(
+    tickers: impl IntoIterator<Item = Ticker>,
+    decimals: u32,
+) -> HashMap<NetworkName, Price> {
+    let scale = math::powi(10.0, i32::try_from(decimals).unwrap_or(i32::MAX));
+    let round = |avg: f64| {
+        let scaled = avg * scale;
+        // beyond 2^52 every `f64` is an integer already
+        if scaled.abs() >= 4_503_599_627_370_496.0 {
+            avg
+        } else {
+            math::round_ties_even(scaled) / scale
+        }
+    };
+
+    tickers
+        .into_iter()
+        .fold(HashMap::new(), |mut sums, val| {
+            add_to_sums(&mut sums, expect_network(&val.symbol), val.price);
+            sums
+        })
+        .into_iter()
+        .filter_map(|(name, partial): (_, PartialAggregate)| {
+            Some((name, round(partial.mean()?) as Price))
+        })
+        .collect()
+}
+
+/// The ticker count and average price of a network, where no tickers means no average.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NetworkAverage {
+    pub count: u32,
+    /// `None` if `count` is zero.
+    pub avg_price: Option<Price>,
+}
+
+impl From<PartialAggregate> for NetworkAverage {
+    fn from(partial: PartialAggregate) -> Self {
+        Self {
+            count: partial.aggregate_count(),
+            avg_price: partial.finalize(),
+        }
+    }
+}
+
+/// Like [`aggregate_filtered`], but returns a [`NetworkAverage`] for every network, including
+/// those that no kept ticker belongs to.
+///
+/// # Panics
+///
+/// Panics if a kept ticker's symbol isn't known.
+pub fn averages_filtered(
+    tickers: impl IntoIterator<Item = Ticker>,
+    keep: impl Fn(&Ticker) -> bool,
+) -> HashMap<NetworkName, NetworkAverage> {
+    let mut sums: HashMap<_, _> = METADATA
+        .iter()
+        .map(|(name, _, _)| (*name, PartialAggregate::new()))
+        .collect();
+    for val in tickers.into_iter().filter(keep) {
+        add_to_sums(&mut sums, expect_network(&val.symbol), val.price);
+    }
+
+    sums.into_iter()
+        .map(|(name, partial)| (name, partial.into()))
+        .collect()
+}
+
+/// Like [`aggregate_stats_by_network`] reduced to counts and averages, but returns a
+/// [`NetworkAverage`] for every network, so networks without tickers are reported with a count of
+/// 0 and no average instead of being left out.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_all_networks(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, NetworkAverage> {
+    averages_filtered(tickers, |_| true)
+}
+
+/// Per-network price statistics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkStats {
+    /// Number of tickers.
+    pub count: u32,
+    /// Sum of the prices, compensated like in [`sum_by_network`].
+    pub sum: f64,
+    pub avg_price: Price,
+    pub min_price: Price,
+    pub max_price: Price,
+    /// Population standard deviation of the prices.
+    pub stddev_price: Price,
+    /// Price of the network's first ticker, in input order.
+    pub first_price: Price,
+    /// Price of the network's last ticker, in input order.
+    pub last_price: Price,
+}
+
+impl NetworkStats {
+    /// Returns the average price computed from `sum` and `count`, or `None` if `count` is zero,
+    /// e.g. for stats built by hand.
+    ///
+    /// Unlike `avg_price`, which is the running mean of the prices, this divides the sum, so it's
+    /// the same average [`aggregate_by_network_ref`] computes.
+    pub fn avg(&self) -> Option<Price> {
+        (self.count != 0).then(|| (self.sum / f64::from(self.count)) as Price)
+    }
+
+    /// Compares `self` to `other` like `==`, but lets the prices differ by up to
+    /// [`PRICE_TOLERANCE`], scaled by their magnitude once it exceeds 1.
+    ///
+    /// The counts have to be equal.
+    pub fn approx_eq(&self, other: &Self) -> bool {
+        let close =
+            |a: Price, b: Price| approx_eq(a, b, PRICE_TOLERANCE * a.abs().max(b.abs()).max(1.0));
+        self.count == other.count
+            && close(self.sum as Price, other.sum as Price)
+            && close(self.avg_price, other.avg_price)
+            && close(self.min_price, other.min_price)
+            && close(self.max_price, other.max_price)
+            && close(self.stddev_price, other.stddev_price)
+            && close(self.first_price, other.first_price)
+            && close(self.last_price, other.last_price)
+    }
+}
+
+/// Renders `result` as a table with a row per network, sorted by [`NetworkName`], e.g. for logs:
+///
+/// ```text
+/// network   count       avg
+/// Mainnet       2  0.200000
+/// Optimism      1  0.400000
+/// ```
+///
+/// Networks are shown by their [display name](NetworkName::display_name), and averages with six
+/// decimal places. Every line, including the last, ends with a newline.
+pub fn format_table(result: &HashMap<NetworkName, NetworkStats>) -> String {
+    use core::fmt::Write;
+
+    let mut rows: Vec<_> = result
+        .iter()
+        .map(|(name, stats)| {
+            let count = alloc::format!("{}", stats.count);
+            let avg = alloc::format!("{:.6}", stats.avg_price);
+            (*name, name.display_name(), count, avg)
+        })
+        .collect();
+    rows.sort_unstable_by_key(|(name, ..)| *name);
+
+    let header = ("network", "count", "avg");
+    let (name_width, count_width, avg_width) = rows.iter().fold(
+        (header.0.len(), header.1.len(), header.2.len()),
+        |(name_width, count_width, avg_width), (_, name, count, avg)| {
+            (
+                name_width.max(name.chars().count()),
+                count_width.max(count.len()),
+                avg_width.max(avg.len()),
+            )
+        },
+    );
+
+    let mut table = String::new();
+    // writing to a `String` can't fail
+    let mut line = |name: &str, count: &str, avg: &str| {
+        let _ = writeln!(
+            table,
+            "{name:<name_width$}  {count:>count_width$}  {avg:>avg_width$}"
+        );
+    };
+    line(header.0, header.1, header.2);
+    for (_, name, count, avg) in &rows {
+        line(name, count, avg);
+    }
+    table
+}
+
+/// The tolerance for comparing prices computed by this crate around 1.0 to expected values.
+///
+/// Averages are computed in `f64` and only narrowed at the end, so they're within half an `f32`
+/// ulp of the exact value. An expected value written as an `f32` literal is off by as much, so the
+/// two can differ by one `f32` epsilon. Scale it with the magnitude of the prices, as
+/// [`NetworkStats::approx_eq`] does.
+pub const PRICE_TOLERANCE: Price = f32::EPSILON as Price;
+
+/// Returns `true` if `a` and `b` differ by at most `tol`.
+///
+/// Equal infinities are equal, `NaN` is never equal to anything.
+pub fn approx_eq(a: Price, b: Price, tol: Price) -> bool {
+    a == b || (a - b).abs() <= tol
+}
+
+/// Groups `tickers` by network and returns [`NetworkStats`] per network.
+///
+/// Everything is computed in a single pass: the mean and the standard deviation use Welford's
+/// online algorithm in `f64`, so no prices are buffered.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_stats_by_network(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, NetworkStats> {
+    stats_by_network(tickers)
+}
+
+/// Returns the spread of the prices per network, its maximum minus its minimum. A network with a
+/// single ticker has a range of `0.0`.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn price_range_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, Price> {
+    stats_by_network(tickers)
+        .into_iter()
+        .map(|(name, stats)| (name, stats.max_price - stats.min_price))
+        .collect()
+}
+
+/// Returns how much the average price of every network moved relative to `baseline`, e.g.
+/// yesterday's averages, in percent: `(avg - base) / base * 100`.
+///
+/// Networks without tickers, missing from `baseline` or with a zero baseline are left out.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn pct_change_vs_baseline(
+    tickers: &[Ticker],
+    baseline: &HashMap<NetworkName, Price>,
+) -> HashMap<NetworkName, Price> {
+    aggregate_by_network_ref(tickers)
+        .into_iter()
+        .filter_map(|(name, (_, avg))| {
+            let base = f64::from(*baseline.get(&name)?);
+            (base != 0.0).then(|| (name, ((f64::from(avg) - base) / base * 100.0) as Price))
+        })
+        .collect()
+}
+
+/// Returns the earliest and the latest timestamp seen per network, e.g. to find networks whose
+/// data has gone stale. A network with a single ticker has equal first and last timestamps.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn time_range_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, (Timestamp, Timestamp)> {
+    let mut result = HashMap::<NetworkName, (Timestamp, Timestamp)>::new();
+    for val in tickers {
+        result
+            .entry(expect_network(&val.symbol))
+            .and_modify(|(first, last)| {
+                *first = (*first).min(val.timestamp);
+                *last = (*last).max(val.timestamp);
+            })
+            .or_insert((val.timestamp, val.timestamp));
+    }
+    result
+}
+
+/// Per-network price statistics from a single pass, see [`full_stats_by_network`].
+///
+/// Unlike [`NetworkStats`], the moments are kept at `f64` precision.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FullStats {
+    /// Number of tickers.
+    pub count: u32,
+    /// The sum of the prices, compensated like in [`sum_by_network`].
+    pub sum: f64,
+    pub mean: f64,
+    pub min: Price,
+    pub max: Price,
+    /// Population variance of the prices.
+    pub variance: f64,
+    /// Population standard deviation of the prices, the square root of `variance`.
+    pub stddev: f64,
+}
+
+/// Computes the count, sum, mean, minimum, maximum and variance of the prices per network in a
+/// single pass, instead of calling [`count_by_network`], [`sum_by_network`],
+/// [`aggregate_stats_by_network`] and friends one after another.
+///
+/// The mean and variance are updated with Welford's algorithm, like in
+/// [`aggregate_stats_by_network`].
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn full_stats_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, FullStats> {
+    accumulate_stats(tickers)
+        .into_iter()
+        .map(|(key, acc)| {
+            let variance = acc.m2 / acc.sums.count() as f64;
+            let stats = FullStats {
+                count: acc.sums.aggregate_count(),
+                sum: acc.sums.sum(),
+                mean: acc.mean,
+                min: acc.min,
+                max: acc.max,
+                variance,
+                stddev: math::sqrt(variance),
+            };
+            (key, stats)
+        })
+        .collect()
+}
+
+// `aggregate_stats_by_network` over owned or borrowed tickers
+fn stats_by_network<T: Borrow<Ticker>>(
+    tickers: impl IntoIterator<Item = T>,
+) -> HashMap<NetworkName, NetworkStats> {
+    accumulate_stats(tickers)
+        .into_iter()
+        .map(|(key, acc)| {
+            let stats = NetworkStats {
+                count: acc.sums.aggregate_count(),
+                sum: acc.sums.sum(),
+                avg_price: acc.mean as Price,
+                min_price: acc.min,
+                max_price: acc.max,
+                stddev_price: math::sqrt(acc.m2 / acc.sums.count() as f64) as Price,
+                first_price: acc.first,
+                last_price: acc.last,
+            };
+            (key, stats)
+        })
+        .collect()
+}
+
+struct StatsAccumulator {
+    // the count and the compensated sum, as in `sum_by_network`
+    sums: PartialAggregate,
+    mean: f64,
+    // sum of squared differences from the current mean
+    m2: f64,
+    min: Price,
+    max: Price,
+    first: Price,
+    last: Price,
+}
+
+// the single pass behind `stats_by_network` and `full_stats_by_network`
+fn accumulate_stats<T: Borrow<Ticker>>(
+    tickers: impl IntoIterator<Item = T>,
+) -> HashMap<NetworkName, StatsAccumulator> {
+    tickers.into_iter().fold(
+        HashMap::<NetworkName, StatsAccumulator>::new(),
+        |mut res, val| {
+            let val = val.borrow();
+            let name = expect_network(&val.symbol);
+            let price = f64::from(val.price);
+            res.entry(name)
+                .and_modify(|acc| {
+                    acc.sums.add_price(val.price);
+                    let delta = price - acc.mean;
+                    acc.mean += delta / acc.sums.count() as f64;
+                    acc.m2 += delta * (price - acc.mean);
+                    acc.min = acc.min.min(val.price);
+                    acc.max = acc.max.max(val.price);
+                    acc.last = val.price;
+                })
+                .or_insert_with(|| {
+                    let mut sums = PartialAggregate::new();
+                    sums.add_price(val.price);
+                    StatsAccumulator {
+                        sums,
+                        mean: price,
+                        m2: 0.0,
+                        min: val.price,
+                        max: val.price,
+                        first: val.price,
+                        last: val.price,
+                    }
+                });
+            res
+        },
+    )
+}
+
+/// [`NetworkStats`] per network, collected from an iterator of tickers.
+///
+/// `let stats: StatsByNetwork = tickers.into_iter().collect();` is the same as calling
+/// [`aggregate_stats_by_network`], and the map derefs to its result.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StatsByNetwork {
+    stats: HashMap<NetworkName, NetworkStats>,
+    total: u64,
 }
 
-const TRACK: &[(&str, NetworkName)] = &[
-    ("S1", NetworkName::N1),
-    ("S2", NetworkName::N2),
-    ("s3", NetworkName::N3),
-];
+impl StatsByNetwork {
+    fn new(stats: HashMap<NetworkName, NetworkStats>) -> Self {
+        let total = stats.values().map(|stats| u64::from(stats.count)).sum();
+        Self { stats, total }
+    }
+
+    /// Returns the number of tickers across all networks, which is the length of the input.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the underlying map.
+    pub fn into_inner(self) -> HashMap<NetworkName, NetworkStats> {
+        self.stats
+    }
+
+    /// Moves the stats into a [`BTreeMap`], which iterates in [`NetworkName`] order. The stats are
+    /// moved rather than cloned.
+    pub fn into_sorted(self) -> BTreeMap<NetworkName, NetworkStats> {
+        self.stats.into_iter().collect()
+    }
+
+    /// Iterates over the networks and their stats in arbitrary order, as the underlying
+    /// [`HashMap`] does.
+    pub fn iter(&self) -> impl Iterator<Item = (NetworkName, &NetworkStats)> {
+        self.stats.iter().map(|(name, stats)| (*name, stats))
+    }
+}
+
+/// Iterates in arbitrary order, as the underlying [`HashMap`] does.
+impl IntoIterator for StatsByNetwork {
+    type Item = (NetworkName, NetworkStats);
+    type IntoIter = <HashMap<NetworkName, NetworkStats> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.stats.into_iter()
+    }
+}
+
+impl Deref for StatsByNetwork {
+    type Target = HashMap<NetworkName, NetworkStats>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.stats
+    }
+}
+
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+impl FromIterator<Ticker> for StatsByNetwork {
+    fn from_iter<I: IntoIterator<Item = Ticker>>(tickers: I) -> Self {
+        Self::new(aggregate_stats_by_network(tickers))
+    }
+}
+
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+impl From<&[Ticker]> for StatsByNetwork {
+    fn from(tickers: &[Ticker]) -> Self {
+        Self::new(stats_by_network(tickers))
+    }
+}
+
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+impl From<Vec<Ticker>> for StatsByNetwork {
+    fn from(tickers: Vec<Ticker>) -> Self {
+        Self::new(stats_by_network(tickers))
+    }
+}
+
+// (count, mean, sum of squared differences from the mean) per network with Welford's algorithm, as
+// in `aggregate_stats_by_network`
+fn moments_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, (u32, f64, f64)> {
+    let mut moments = HashMap::<NetworkName, (u32, f64, f64)>::new();
+    for val in tickers {
+        let price = f64::from(val.price);
+        let (count, mean, m2) = moments.entry(expect_network(&val.symbol)).or_default();
+        *count = count.checked_add(1).expect("count doesn't overflow");
+        let delta = price - *mean;
+        *mean += delta / f64::from(*count);
+        *m2 += delta * (price - *mean);
+    }
+    moments
+}
+
+/// Computes the mean price per network, leaving out prices more than `k` standard deviations
+/// away from the network's mean.
+///
+/// This is a two-pass algorithm: the first pass over `tickers` computes the mean and the
+/// population standard deviation per network, the second one averages the prices within
+/// `k` standard deviations of that mean, bounds included. If every price of a network is left
+/// out, e.g. because `k` is negative, the network's raw mean is returned instead.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn robust_average_by_network(tickers: &[Ticker], k: Price) -> HashMap<NetworkName, Price> {
+    let moments = moments_by_network(tickers);
+
+    let mut kept = HashMap::<NetworkName, PartialAggregate>::new();
+    for val in tickers {
+        let name = expect_network(&val.symbol);
+        let (count, mean, m2) = moments[&name];
+        let bound = f64::from(k) * math::sqrt(m2 / f64::from(count));
+        if (f64::from(val.price) - mean).abs() <= bound {
+            kept.entry(name).or_default().add_price(val.price);
+        }
+    }
+
+    moments
+        .into_iter()
+        .map(|(name, (_, mean, _))| {
+            let avg = kept
+                .get(&name)
+                .and_then(PartialAggregate::finalize)
+                .unwrap_or(mean as Price);
+            (name, avg)
+        })
+        .collect()
+}
+
+/// Computes a confidence interval `(lower, upper)` around the mean price per network, `z`
+/// standard errors wide on either side, e.g. `1.96` for 95%.
+///
+/// The standard error is the sample standard deviation over the square root of the count, both
+/// computed in a single pass with Welford's algorithm. Networks with a single ticker have no sample
+/// standard deviation and are left out.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn mean_ci_by_network(tickers: &[Ticker], z: f64) -> HashMap<NetworkName, (Price, Price)> {
+    let moments = moments_by_network(tickers);
+
+    moments
+        .into_iter()
+        .filter(|(_, (count, _, _))| *count > 1)
+        .map(|(name, (count, mean, m2))| {
+            let count = f64::from(count);
+            let std_err = math::sqrt(m2 / (count - 1.0)) / math::sqrt(count);
+            let margin = z * std_err;
+            (name, ((mean - margin) as Price, (mean + margin) as Price))
+        })
+        .collect()
+}
+
+/// Computes the exponentially weighted average price per network, where a ticker's weight halves
+/// with every `half_life` it is older than the network's newest ticker.
+///
+/// The weights are computed from the timestamps relative to the newest ticker, so `tickers` don't
+/// need to be sorted. With a zero `half_life` only the newest tickers count.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn ewma_by_network(tickers: &[Ticker], half_life: Duration) -> HashMap<NetworkName, Price> {
+    let mut newest = HashMap::<NetworkName, Timestamp>::new();
+    for val in tickers {
+        let latest = newest.entry(expect_network(&val.symbol)).or_default();
+        *latest = (*latest).max(val.timestamp);
+    }
+
+    let mut sums = HashMap::<NetworkName, (f64, f64)>::new();
+    for val in tickers {
+        let name = expect_network(&val.symbol);
+        let age = newest[&name] - val.timestamp;
+        let weight = if half_life.is_zero() {
+            if age.is_zero() {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            math::exp(-core::f64::consts::LN_2 * age.as_secs_f64() / half_life.as_secs_f64())
+        };
+        let (weighted, total) = sums.entry(name).or_default();
+        *weighted += weight * f64::from(val.price);
+        *total += weight;
+    }
+
+    sums.into_iter()
+        .map(|(name, (weighted, total))| (name, (weighted / total) as Price))
+        .collect()
+}
+
+/// Computes the median price per network.
+///
+/// For an even number of tickers the median is the mean of the two middle prices. Prices are
+/// ordered with [`f32::total_cmp`], so a `NaN` price sorts above `+inf` (or below `-inf` if its
+/// sign bit is set) instead of breaking the sort.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn median_price_by_network(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, Price> {
+    tickers
+        .into_iter()
+        .fold(HashMap::<NetworkName, Vec<Price>>::new(), |mut res, val| {
+            let name = expect_network(&val.symbol);
+            res.entry(name).or_default().push(val.price);
+            res
+        })
+        .into_iter()
+        .filter(|(_, prices)| !prices.is_empty())
+        .map(|(key, mut prices)| {
+            prices.sort_unstable_by(Price::total_cmp);
+            let mid = prices.len() / 2;
+            let median = if prices.len() % 2 == 0 {
+                ((f64::from(prices[mid - 1]) + f64::from(prices[mid])) / 2.0) as Price
+            } else {
+                prices[mid]
+            };
+            (key, median)
+        })
+        .collect()
+}
+
+/// Computes the most frequent price per network, breaking ties toward the smaller price.
+///
+/// Prices are counted by their bit patterns, so only exactly equal prices count as the same:
+/// prices that differ in the last bit, e.g. because they were computed in different ways, are
+/// counted separately, as are `0.0` and `-0.0`. Ties are ordered with [`f32::total_cmp`].
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn mode_price_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, Price> {
+    tickers
+        .iter()
+        .fold(
+            HashMap::<NetworkName, HashMap<_, u32>>::new(),
+            |mut res, val| {
+                let name = expect_network(&val.symbol);
+                let count = res
+                    .entry(name)
+                    .or_default()
+                    .entry(val.price.to_bits())
+                    .or_default();
+                *count = count.checked_add(1).expect("count doesn't overflow");
+                res
+            },
+        )
+        .into_iter()
+        .filter_map(|(key, counts)| {
+            let (bits, _) = counts.into_iter().max_by(|a, b| {
+                let smaller_price = Price::from_bits(b.0).total_cmp(&Price::from_bits(a.0));
+                a.1.cmp(&b.1).then(smaller_price)
+            })?;
+            Some((key, Price::from_bits(bits)))
+        })
+        .collect()
+}
+
+/// Computes the volume-weighted average price (`sum(price * volume) / sum(volume)`) per network.
+///
+/// Networks whose tickers add up to zero volume have no meaningful VWAP and are left out.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn vwap_by_network(tickers: impl IntoIterator<Item = Ticker>) -> HashMap<NetworkName, f64> {
+    tickers
+        .into_iter()
+        .fold(HashMap::<NetworkName, (f64, f64)>::new(), |mut res, val| {
+            let name = expect_network(&val.symbol);
+            let entry = res.entry(name).or_default();
+            entry.0 += f64::from(val.price) * val.volume;
+            entry.1 += val.volume;
+            res
+        })
+        .into_iter()
+        .filter(|(_, (_, volume))| *volume != 0.0)
+        .map(|(key, (value, volume))| (key, value / volume))
+        .collect()
+}
+
+/// Computes the volume-weighted median price per network: the price at which the cumulative
+/// volume, walking the prices in ascending order, first exceeds half of the network's total
+/// volume.
+///
+/// If the cumulative volume hits exactly half at some price, the median is the mean of that price
+/// and the next higher price with volume, so with equal volumes this matches
+/// [`median_price_by_network`]. Tickers with zero volume don't contribute, and networks whose
+/// tickers add up to zero volume are left out. Prices are ordered with [`f32::total_cmp`].
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn weighted_median_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, Price> {
+    tickers
+        .iter()
+        .fold(
+            HashMap::<NetworkName, Vec<(Price, f64)>>::new(),
+            |mut res, val| {
+                let name = expect_network(&val.symbol);
+                res.entry(name).or_default().push((val.price, val.volume));
+                res
+            },
+        )
+        .into_iter()
+        .filter_map(|(key, mut entries)| {
+            let half = entries.iter().map(|(_, volume)| volume).sum::<f64>() / 2.0;
+            if half <= 0.0 {
+                return None;
+            }
+            entries.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut cumulative = 0.0;
+            // the price at which the cumulative volume hit exactly half
+            let mut lower = None;
+            for (price, volume) in entries.into_iter().filter(|(_, volume)| *volume != 0.0) {
+                if let Some(lower) = lower {
+                    let median = (f64::from(lower) + f64::from(price)) / 2.0;
+                    return Some((key, median as Price));
+                }
+                cumulative += volume;
+                if cumulative == half {
+                    lower = Some(price);
+                } else if cumulative > half {
+                    return Some((key, price));
+                }
+            }
+            // only reached through rounding in the cumulative sum
+            lower.map(|lower| (key, lower))
+        })
+        .collect()
+}
+
+/// Computes the volume-weighted variance of the price per network, treating the volumes as
+/// reliability weights.
+///
+/// The weighted sum of squared differences from the weighted mean is divided by
+/// `V1 - V2 / V1`, where `V1` is the sum of the volumes and `V2` the sum of their squares. This is
+/// the unbiased estimator, and matches the sample variance when all volumes are equal. Networks
+/// where that denominator is zero, e.g. with a single ticker or zero total volume, are left out.
+///
+/// The weighted mean and the sum of squares are updated in a single pass with West's weighted
+/// variant of Welford's algorithm.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn weighted_variance_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, f64> {
+    // (sum of weights, sum of squared weights, weighted mean, weighted sum of squared differences)
+    let mut moments = HashMap::<NetworkName, (f64, f64, f64, f64)>::new();
+    for val in tickers {
+        let (price, weight) = (f64::from(val.price), val.volume);
+        let (v1, v2, mean, s) = moments.entry(expect_network(&val.symbol)).or_default();
+        *v1 += weight;
+        *v2 += weight * weight;
+        if *v1 != 0.0 {
+            let delta = price - *mean;
+            *mean += delta * weight / *v1;
+            *s += weight * delta * (price - *mean);
+        }
+    }
+
+    moments
+        .into_iter()
+        .filter_map(|(name, (v1, v2, _, s))| {
+            let denominator = if v1 == 0.0 { 0.0 } else { v1 - v2 / v1 };
+            (denominator > 0.0).then(|| (name, s / denominator))
+        })
+        .collect()
+}
+
+/// Computes the `p`th percentile of the price per network, with `p` in `[0.0, 100.0]`.
+///
+/// Values between the closest ranks are linearly interpolated, so a single-ticker network returns
+/// its price for any `p`. Prices are ordered with [`f32::total_cmp`], as in
+/// [`median_price_by_network`].
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn percentile_by_network(
+    tickers: &[Ticker],
+    p: f64,
+) -> Result<HashMap<NetworkName, Price>, InvalidPercentileError> {
+    if !(0.0..=100.0).contains(&p) {
+        return Err(InvalidPercentileError(p));
+    }
+
+    let result = tickers
+        .iter()
+        .fold(HashMap::<NetworkName, Vec<Price>>::new(), |mut res, val| {
+            let name = expect_network(&val.symbol);
+            res.entry(name).or_default().push(val.price);
+            res
+        })
+        .into_iter()
+        .map(|(key, mut prices)| {
+            prices.sort_unstable_by(Price::total_cmp);
+            let rank = p / 100.0 * (prices.len() - 1) as f64;
+            // `rank` is within `[0, len - 1]`, so truncating gives the lower index
+            let low = rank as usize;
+            let high = (low + 1).min(prices.len() - 1);
+            let fraction = rank - low as f64;
+            let value = f64::from(prices[low])
+                + (f64::from(prices[high]) - f64::from(prices[low])) * fraction;
+            (key, value as Price)
+        })
+        .collect();
+
+    Ok(result)
+}
+
+/// The error returned by [`percentile_by_network`] for a percentile outside `[0.0, 100.0]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InvalidPercentileError(pub f64);
+
+impl fmt::Display for InvalidPercentileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "percentile {} is outside of [0, 100]", self.0)
+    }
+}
+
+impl core::error::Error for InvalidPercentileError {}
+
+/// Counts the prices of every network in buckets of `bucket_width`, as `(lower_bound, count)`
+/// pairs.
+///
+/// A network's buckets start at its lowest price and cover everything up to its highest, so empty
+/// buckets in between are included with a count of zero. Non-finite prices are left out. Each
+/// network's prices are buffered, and a `bucket_width` that is tiny compared to the price range
+/// allocates a bucket for every step.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn price_histogram_by_network(
+    tickers: &[Ticker],
+    bucket_width: Price,
+) -> Result<HashMap<NetworkName, Vec<(Price, u32)>>, InvalidBucketWidthError> {
+    if !bucket_width.is_finite() || bucket_width <= 0.0 {
+        return Err(InvalidBucketWidthError(bucket_width));
+    }
+    let width = f64::from(bucket_width);
+
+    let result = tickers
+        .iter()
+        .fold(HashMap::<NetworkName, Vec<f64>>::new(), |mut res, val| {
+            let name = expect_network(&val.symbol);
+            if val.price.is_finite() {
+                res.entry(name).or_default().push(f64::from(val.price));
+            }
+            res
+        })
+        .into_iter()
+        .filter(|(_, prices)| !prices.is_empty())
+        .map(|(key, prices)| {
+            let min = prices.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = prices.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            // the offsets aren't negative, so truncating them gives the bucket index
+            let mut counts = alloc::vec![0; ((max - min) / width) as usize + 1];
+            for price in prices {
+                counts[((price - min) / width) as usize] += 1;
+            }
+            let buckets = counts
+                .into_iter()
+                .enumerate()
+                .map(|(i, count)| ((min + i as f64 * width) as Price, count))
+                .collect();
+            (key, buckets)
+        })
+        .collect();
+
+    Ok(result)
+}
+
+/// The error returned by [`price_histogram_by_network`] for a bucket width that isn't a positive
+/// finite number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InvalidBucketWidthError(pub Price);
+
+impl fmt::Display for InvalidBucketWidthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bucket width {} isn't a positive number", self.0)
+    }
+}
+
+impl core::error::Error for InvalidBucketWidthError {}
+
+/// Computes the geometric mean of the price per network, i.e. `exp(mean(ln(price)))`.
+///
+/// The geometric mean is only defined for positive prices, so this fails with
+/// [`AggregateError::InvalidPrice`] on the first price that is zero, negative or not finite, and
+/// with [`AggregateError::UnknownSymbol`] on the first unknown symbol.
+pub fn geometric_mean_by_network(
+    tickers: &[Ticker],
+) -> Result<HashMap<NetworkName, Price>, AggregateError> {
+    let mut sums = HashMap::<NetworkName, (u32, f64)>::new();
+    for val in tickers {
+        let name = lookup_symbol(&val.symbol)
+            .ok_or_else(|| AggregateError::UnknownSymbol(val.symbol.clone().into_owned()))?;
+        if !val.price.is_finite() || val.price <= 0.0 {
+            return Err(AggregateError::InvalidPrice {
+                symbol: val.symbol.clone().into_owned(),
+                price: val.price,
+            });
+        }
+        let entry = sums.entry(name).or_default();
+        entry.0 = entry
+            .0
+            .checked_add(1)
+            .ok_or(AggregateError::CountOverflow(name))?;
+        entry.1 += math::ln(f64::from(val.price));
+    }
+
+    let result = sums
+        .into_iter()
+        .map(|(key, (count, ln_sum))| (key, math::exp(ln_sum / f64::from(count)) as Price))
+        .collect();
+    Ok(result)
+}
+
+/// Computes the mean bid-ask spread per network over the tickers that carry a [`Quote`].
+///
+/// Fails with [`AggregateError::InvalidSpread`] on the first quote whose ask is below its bid, and
+/// with [`AggregateError::UnknownSymbol`] on the first unknown symbol. Networks without quotes are
+/// left out.
+pub fn avg_spread_by_network(
+    tickers: &[Ticker],
+) -> Result<HashMap<NetworkName, Price>, AggregateError> {
+    let mut sums = HashMap::<NetworkName, PartialAggregate>::new();
+    for val in tickers {
+        let Some(quote) = val.quote else {
+            continue;
+        };
+        let name = lookup_symbol(&val.symbol)
+            .ok_or_else(|| AggregateError::UnknownSymbol(val.symbol.clone().into_owned()))?;
+        if quote.ask < quote.bid {
+            return Err(AggregateError::InvalidSpread {
+                symbol: val.symbol.clone().into_owned(),
+                bid: quote.bid,
+                ask: quote.ask,
+            });
+        }
+        sums.entry(name).or_default().add_price(quote.spread());
+    }
+
+    let result = sums
+        .into_iter()
+        .filter_map(|(name, partial)| Some((name, partial.finalize()?)))
+        .collect();
+    Ok(result)
+}
+
+/// Computes the harmonic mean of the price per network, i.e. `count / sum(1 / price)`.
+///
+/// Like the geometric mean, the harmonic mean is only defined here for positive prices, so this
+/// fails with [`AggregateError::InvalidPrice`] on the first price that is zero, negative or not
+/// finite, and with [`AggregateError::UnknownSymbol`] on the first unknown symbol.
+pub fn harmonic_mean_by_network(
+    tickers: &[Ticker],
+) -> Result<HashMap<NetworkName, Price>, AggregateError> {
+    let mut sums = HashMap::<NetworkName, (u32, f64)>::new();
+    for val in tickers {
+        let name = lookup_symbol(&val.symbol)
+            .ok_or_else(|| AggregateError::UnknownSymbol(val.symbol.clone().into_owned()))?;
+        if !val.price.is_finite() || val.price <= 0.0 {
+            return Err(AggregateError::InvalidPrice {
+                symbol: val.symbol.clone().into_owned(),
+                price: val.price,
+            });
+        }
+        let entry = sums.entry(name).or_default();
+        entry.0 = entry
+            .0
+            .checked_add(1)
+            .ok_or(AggregateError::CountOverflow(name))?;
+        entry.1 += 1.0 / f64::from(val.price);
+    }
+
+    let result = sums
+        .into_iter()
+        .map(|(key, (count, reciprocal_sum))| (key, (f64::from(count) / reciprocal_sum) as Price))
+        .collect();
+    Ok(result)
+}
+
+/// Counts the tickers of each network, without touching their prices.
+///
+/// Symbols are resolved like in [`aggregate_by_network_ref`], and the counts are kept on the stack.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known, or if a count would overflow.
+pub fn count_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, u32> {
+    // indexed by variant, which is also the order of `METADATA`
+    let mut counts = [0u32; METADATA.len()];
+    for val in tickers {
+        let count = &mut counts[expect_network(&val.symbol) as usize];
+        *count = count.checked_add(1).expect("count doesn't overflow");
+    }
+
+    METADATA
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .map(|((name, _, _), count)| (*name, count))
+        .collect()
+}
+
+/// Counts the distinct symbols that contributed to each network.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn distinct_symbols_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, usize> {
+    tickers
+        .iter()
+        .fold(
+            HashMap::<NetworkName, HashSet<&str>>::new(),
+            |mut res, val| {
+                let name = expect_network(&val.symbol);
+                res.entry(name).or_default().insert(&val.symbol);
+                res
+            },
+        )
+        .into_iter()
+        .map(|(key, symbols)| (key, symbols.len()))
+        .collect()
+}
+
+/// Computes the count and average price per symbol, grouped by network.
+///
+/// Summing the inner counts of a network gives its count in [`aggregate_sorted`]. The symbols are
+/// borrowed from the symbol table, like the keys of [`name_from_symbol`]'s cache, so the keys
+/// don't allocate.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn aggregate_by_network_and_symbol(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, HashMap<Cow<'static, str>, NetworkAggregate>> {
+    tickers
+        .into_iter()
+        .fold(
+            HashMap::<NetworkName, HashMap<Cow<'static, str>, PartialAggregate>>::new(),
+            |mut res, val| {
+                let (symbol, name) = track_entry(&val.symbol).expect("symbol is valid");
+                add_to_sums(res.entry(name).or_default(), symbol.into(), val.price);
+                res
+            },
+        )
+        .into_iter()
+        .map(|(key, sums)| (key, averages(sums)))
+        .collect()
+}
+
+/// The error returned by the fallible aggregation functions.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregateError {
+    /// A ticker carried a symbol that doesn't belong to any network.
+    UnknownSymbol(String),
+    /// A ticker's price failed validation.
+    InvalidPrice { symbol: String, price: Price },
+    /// A network received more tickers than its count can represent.
+    CountOverflow(NetworkName),
+    /// A ticker was removed from a network that had no tickers left.
+    CountUnderflow(NetworkName),
+    /// A ticker's currency had no exchange rate.
+    UnknownCurrency(Currency),
+    /// An [`AggregatorSet`] has no aggregator for the tenant.
+    UnknownTenant(String),
+    /// A ticker's quote had its ask below its bid.
+    InvalidSpread {
+        symbol: String,
+        bid: Price,
+        ask: Price,
+    },
+    /// The ticker at `index` in the input was rejected for `reason`.
+    ///
+    /// [`try_aggregate_by_network`], [`try_aggregate_by_network_with`],
+    /// [`try_aggregate_by_network_cached`], [`aggregate_with_classifier`],
+    /// [`aggregate_with_normalizer`] and [`Aggregator::aggregate`] wrap every error in this, so the
+    /// offending ticker can be found in the input.
+    InvalidTicker {
+        index: usize,
+        reason: Box<AggregateError>,
+    },
+}
+
+impl AggregateError {
+    /// Returns the error without the position of the ticker, i.e. the `reason` of
+    /// [`InvalidTicker`](Self::InvalidTicker), or `self` for every other error.
+    pub fn reason(&self) -> &Self {
+        match self {
+            Self::InvalidTicker { reason, .. } => reason.reason(),
+            _ => self,
+        }
+    }
+}
+
+impl fmt::Display for AggregateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSymbol(symbol) => write!(f, "unknown symbol `{symbol}`"),
+            Self::InvalidPrice { symbol, price } => {
+                write!(f, "invalid price {price} for symbol `{symbol}`")
+            }
+            Self::CountOverflow(name) => write!(f, "ticker count overflow for network {name}"),
+            Self::CountUnderflow(name) => write!(f, "ticker count underflow for network {name}"),
+            Self::UnknownCurrency(currency) => {
+                write!(f, "no exchange rate for currency `{currency}`")
+            }
+            Self::UnknownTenant(tenant) => write!(f, "unknown tenant `{tenant}`"),
+            Self::InvalidSpread { symbol, bid, ask } => {
+                write!(f, "ask {ask} below bid {bid} for symbol `{symbol}`")
+            }
+            Self::InvalidTicker { index, reason } => write!(f, "ticker {index}: {reason}"),
+        }
+    }
+}
+
+impl core::error::Error for AggregateError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::InvalidTicker { reason, .. } => Some(reason.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseNetworkNameError> for AggregateError {
+    fn from(err: ParseNetworkNameError) -> Self {
+        Self::UnknownSymbol(err.symbol)
+    }
+}
+
+/// Resolves `symbol` to its network, memoizing the lookup in `cache`.
+///
+/// # Panics
+///
+/// Panics if `symbol` isn't known. Use [`try_name_from_symbol`] for untrusted input.
+pub fn name_from_symbol(symbol: &str, cache: &mut HashMap<&str, NetworkName>) -> NetworkName {
+    try_name_from_symbol(symbol, cache).expect("symbol is valid")
+}
+
+/// Resolves `symbol` to its network, memoizing the lookup in `cache`.
+///
+/// Leading and trailing ASCII whitespace in `symbol` is ignored. Returns `None` if `symbol` isn't
+/// known.
+///
+/// For the built-in table the cache doesn't pay off: hashing `symbol` costs more than
+/// [`lookup_symbol`] does without a cache, which is why the aggregations resolve symbols with
+/// it. The cache only saves work for tables that are expensive to search.
+pub fn try_name_from_symbol(
+    symbol: &str,
+    cache: &mut HashMap<&str, NetworkName>,
+) -> Option<NetworkName> {
+    // feeds sometimes pad symbols. The trimmed input is only used for the lookup, the cache still
+    // gets the 'static key from TRACK
+    let symbol = symbol.trim_ascii();
+
+    // the keys inside TRACK are 'static. With methods like `.entry(...).insert_...` we couldn't
+    // just use keys from TRACK, as we would need to clone the argument (`symbol`).
+    if !cache.contains_key(symbol) {
+        let Some((key, value)) = track_entry(symbol) else {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(symbol, "unknown symbol");
+            return None;
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(symbol, network = ?value, "symbol cache miss");
+        cache.insert(key, value);
+    }
+
+    Some(cache[symbol])
+}
+
+/// Like [`name_from_symbol`], but matches `symbol` against the known symbols ignoring case, see
+/// [`try_name_from_symbol_ci`].
+///
+/// # Panics
+///
+/// Panics if `symbol` isn't known.
+pub fn name_from_symbol_ci(symbol: &str, cache: &mut HashMap<&str, NetworkName>) -> NetworkName {
+    try_name_from_symbol_ci(symbol, cache).expect("symbol is valid")
+}
+
+/// Like [`try_name_from_symbol`], but matches `symbol` against the known symbols ignoring case.
+///
+/// By default only ASCII letters are folded, so `"s1"` matches `"S1"` but `"Ä"` doesn't match
+/// `"ä"`. With the `unicode` feature both sides are lowercased with [`char::to_lowercase`], which
+/// folds every letter with a Unicode lowercase mapping. The mapping is applied per character, so
+/// context-dependent rules, like the final sigma of [`str::to_lowercase`], and locale-specific
+/// rules, like the Turkish dotless i, don't apply.
+///
+/// The cache is still keyed by the `'static` symbols from `TRACK`, so only lookups spelled exactly
+/// like a known symbol hit it. Other spellings fold `symbol` and look it up in a map of the folded
+/// known symbols, which is built on first use, or scan the known symbols without `std`.
+pub fn try_name_from_symbol_ci(
+    symbol: &str,
+    cache: &mut HashMap<&str, NetworkName>,
+) -> Option<NetworkName> {
+    let symbol = symbol.trim_ascii();
+
+    if let Some(name) = cache.get(symbol) {
+        return Some(*name);
+    }
+
+    let (key, value) = track_entry_ci(symbol)?;
+    cache.insert(key, value);
+
+    Some(value)
+}
+
+// like `track_entry`, but ignoring case. The folded symbols are hashed once, so a lookup only folds
+// `symbol` and probes the map
+#[cfg(feature = "std")]
+fn track_entry_ci(symbol: &str) -> Option<(&'static str, NetworkName)> {
+    use std::sync::OnceLock;
+
+    static FOLDED_TRACK: OnceLock<HashMap<String, (&'static str, NetworkName)>> = OnceLock::new();
+    let folded = FOLDED_TRACK.get_or_init(|| {
+        let mut folded = HashMap::with_capacity(TRACK.len());
+        for entry in TRACK {
+            // the first of the symbols that only differ in case wins, like in the scan
+            folded.entry(fold_case(entry.0)).or_insert(*entry);
+        }
+        folded
+    });
+    folded.get(fold_case(symbol).as_str()).copied()
+}
+
+// without `std` there's no `OnceLock` to build the folded map in, so `TRACK` is scanned
+#[cfg(not(feature = "std"))]
+fn track_entry_ci(symbol: &str) -> Option<(&'static str, NetworkName)> {
+    TRACK
+        .iter()
+        .find(|(sym, _)| eq_ignore_case(sym, symbol))
+        .copied()
+}
+
+// the key of `symbol` in the folded `TRACK`, equal for two symbols iff `eq_ignore_case` is true
+#[cfg(any(feature = "std", test))]
+fn fold_case(symbol: &str) -> String {
+    #[cfg(feature = "unicode")]
+    return symbol.chars().flat_map(char::to_lowercase).collect();
+
+    #[cfg(not(feature = "unicode"))]
+    symbol.to_ascii_lowercase()
+}
+
+// the case folding of `try_name_from_symbol_ci`
+#[cfg(any(not(feature = "std"), test))]
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    #[cfg(feature = "unicode")]
+    return a
+        .chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase));
+
+    #[cfg(not(feature = "unicode"))]
+    a.eq_ignore_ascii_case(b)
+}
+
+/// Resolves every symbol in `symbols` like [`try_name_from_symbol`], sharing one cache across the
+/// batch, e.g. to reject a batch with unexpected symbols before aggregating it.
+///
+/// Returns the network of every known symbol, keyed by the symbol as given, and the unknown
+/// symbols in the order they first occur, each listed once.
+pub fn classify_all<'a>(symbols: &[&'a str]) -> (HashMap<&'a str, NetworkName>, Vec<&'a str>) {
+    let mut cache = HashMap::new();
+    let mut known = HashMap::new();
+    let mut unknown = Vec::new();
+    for symbol in symbols {
+        match try_name_from_symbol(symbol, &mut cache) {
+            Some(name) => {
+                known.insert(*symbol, name);
+            }
+            None if !unknown.contains(symbol) => unknown.push(*symbol),
+            None => {}
+        }
+    }
+    (known, unknown)
+}
+
+/// A reusable cache for [`try_name_from_symbol`].
+///
+/// The cache is keyed by the `'static` symbols from `TRACK` rather than by the strings it was
+/// queried with, so it borrows nothing from its callers and can be kept for as long as needed.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolCache {
+    map: HashMap<&'static str, NetworkName>,
+    stats: Option<LookupStats>,
+}
+
+/// How the lookups of a [`SymbolCache`] created [`with_stats`](SymbolCache::with_stats) went, e.g.
+/// during [`try_aggregate_by_network_cached`], or those of an [`Aggregator`] built
+/// [`with_lookup_stats`](AggregatorBuilder::with_lookup_stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LookupStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups of known symbols that weren't cached yet, so the table was searched.
+    pub misses: u64,
+    /// Lookups of symbols that aren't known. They're never cached, so the table is searched every
+    /// time.
+    pub unknown: u64,
+}
+
+impl SymbolCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a cache that counts its hits and misses, see [`stats`](Self::stats).
+    ///
+    /// Caches created with [`new`](Self::new) don't count anything.
+    pub fn with_stats() -> Self {
+        Self {
+            stats: Some(LookupStats::default()),
+            ..Self::default()
+        }
+    }
+
+    /// Resolves `symbol` to its network like [`try_name_from_symbol`], memoizing the lookup.
+    pub fn resolve(&mut self, symbol: &str) -> Option<NetworkName> {
+        let cached = self.map.len();
+        let name = try_name_from_symbol(symbol, &mut self.map);
+        if let Some(stats) = &mut self.stats {
+            // a miss of a known symbol always caches it
+            let counter = match name {
+                Some(_) if self.map.len() == cached => &mut stats.hits,
+                Some(_) => &mut stats.misses,
+                None => &mut stats.unknown,
+            };
+            *counter += 1;
+        }
+        name
+    }
+
+    /// Returns the counts of the lookups so far, or `None` if the cache wasn't created
+    /// [`with_stats`](Self::with_stats).
+    pub fn stats(&self) -> Option<LookupStats> {
+        self.stats
+    }
+
+    /// Returns the number of cached symbols.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if nothing is cached yet.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// The time a [`Ticker`] was observed, as the time elapsed since an epoch of the caller's choice,
+/// e.g. the Unix epoch.
+pub type Timestamp = Duration;
+
+/// A single price observation for a symbol.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ticker {
+    /// Borrowed for symbols from [`Ticker::from_static`], owned otherwise.
+    pub symbol: Cow<'static, str>,
+    pub price: Price,
+    /// Traded volume at `price`. Only volume-weighted statistics look at it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub volume: f64,
+    /// The bid and ask behind `price`, if the feed provides them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub quote: Option<Quote>,
+    /// When the price was observed. Only time-based aggregations look at it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timestamp: Timestamp,
+    /// The currency `price` is quoted in, if it isn't the base currency.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub currency: Option<Currency>,
+    /// Whether `price` was traded or only quoted, see [`aggregate_by_network_for`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: TickerKind,
+}
+
+impl Ticker {
+    /// Creates a trade ticker with zero volume.
+    pub fn new(symbol: impl Into<String>, price: Price) -> Self {
+        Self::with_symbol(Cow::Owned(symbol.into()), price)
+    }
+
+    /// Like [`Ticker::new`], but borrows `symbol` instead of allocating a copy of it, e.g. for
+    /// string literals.
+    pub const fn from_static(symbol: &'static str, price: Price) -> Self {
+        Self::with_symbol(Cow::Borrowed(symbol), price)
+    }
+
+    const fn with_symbol(symbol: Cow<'static, str>, price: Price) -> Self {
+        Self {
+            symbol,
+            price,
+            volume: 0.0,
+            quote: None,
+            timestamp: Timestamp::ZERO,
+            currency: None,
+            kind: TickerKind::Trade,
+        }
+    }
+
+    /// Creates a [`TickerKind::Quote`] ticker with zero volume from a quote, with the mid-price as
+    /// its `price`.
+    pub fn from_quote(symbol: impl Into<String>, bid: Price, ask: Price) -> Self {
+        let mid = (f64::from(bid) + f64::from(ask)) / 2.0;
+        Self {
+            quote: Some(Quote { bid, ask }),
+            kind: TickerKind::Quote,
+            ..Self::new(symbol, mid as Price)
+        }
+    }
+
+    /// Sets the traded volume.
+    pub fn with_volume(mut self, volume: f64) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Sets the time the price was observed.
+    pub fn with_timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets the currency the price is quoted in.
+    pub fn with_currency(mut self, currency: impl Into<Currency>) -> Self {
+        self.currency = Some(currency.into());
+        self
+    }
+
+    /// Sets whether the price was traded or only quoted.
+    pub fn with_kind(mut self, kind: TickerKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// Whether a [`Ticker`] is an executed trade or an indicative quote.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TickerKind {
+    #[default]
+    Trade,
+    Quote,
+}
+
+/// The best bid and ask of a [`Ticker`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quote {
+    pub bid: Price,
+    pub ask: Price,
+}
+
+impl Quote {
+    /// Returns `ask - bid`, which is negative for a crossed quote.
+    pub fn spread(self) -> Price {
+        self.ask - self.bid
+    }
+}
+
+/// A [`Ticker`] that borrows its symbol, e.g. from the buffer it was parsed from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TickerRef<'a> {
+    pub symbol: &'a str,
+    pub price: Price,
+}
+
+impl<'a> From<&'a Ticker> for TickerRef<'a> {
+    fn from(ticker: &'a Ticker) -> Self {
+        Self {
+            symbol: &ticker.symbol,
+            price: ticker.price,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_float_eq::assert_float_absolute_eq;
+
+    // the expected values are only `f32` precise, with `f64-prices` the results are closer still
+    const EPSILON: Price = f32::EPSILON as Price;
+
+    #[test]
+    #[allow(deprecated)]
+    fn aggregate_by_network_empty() {
+        assert!(aggregate_by_network(vec![]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_sorted_empty() {
+        assert!(aggregate_sorted(vec![]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_btreemap_empty() {
+        assert!(aggregate_btreemap(vec![]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_with_callback_empty() {
+        assert!(aggregate_with_callback(vec![], |_, _| unreachable!()).is_empty());
+    }
+
+    #[test]
+    fn aggregate_filtered_empty() {
+        assert!(aggregate_filtered(vec![], |_| true).is_empty());
+    }
+
+    #[test]
+    fn top_networks_by_count_empty() {
+        assert!(top_networks_by_count(vec![], 3).is_empty());
+    }
+
+    #[test]
+    fn aggregate_lenient_empty() {
+        assert!(aggregate_lenient(vec![]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_lenient_with_report_empty() {
+        let (result, unknown) = aggregate_lenient_with_report(vec![]);
+
+        assert!(result.is_empty());
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn try_aggregate_by_network_empty() {
+        assert_eq!(Ok(HashMap::new()), try_aggregate_by_network(vec![]));
+    }
+
+    #[test]
+    fn aggregate_by_network_ref_empty() {
+        assert!(aggregate_by_network_ref(&[]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_grouped_empty() {
+        assert!(aggregate_grouped(&[], |ticker| ticker.price > 1.0).is_empty());
+    }
+
+    #[test]
+    fn aggregate_ticker_refs_empty() {
+        assert!(aggregate_ticker_refs(&[]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_dedup_empty() {
+        assert!(aggregate_dedup(&[]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_chunked_empty() {
+        assert!(aggregate_chunked(&[], 2, |_| true).is_empty());
+    }
+
+    #[test]
+    fn aggregate_with_overall_empty() {
+        let (result, overall) = aggregate_with_overall(vec![]);
+
+        assert!(result.is_empty());
+        assert_eq!(None, overall);
+    }
+
+    #[test]
+    fn aggregate_all_empty() {
+        assert!(aggregate_all(vec![]).is_empty());
+        assert!(aggregate_all(vec![vec![], vec![]]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_rounded_empty() {
+        assert!(aggregate_rounded(vec![], 2).is_empty());
+    }
+
+    #[test]
+    fn aggregate_all_networks_empty() {
+        let result = aggregate_all_networks(vec![]);
+
+        assert_eq!(METADATA.len(), result.len());
+        assert!(result.values().all(|average| average.count == 0));
+    }
+
+    #[test]
+    fn averages_filtered_empty() {
+        let result = averages_filtered(vec![], |_| true);
+
+        assert_eq!(METADATA.len(), result.len());
+        assert!(result.values().all(|average| average.avg_price.is_none()));
+    }
+
+    #[test]
+    fn price_range_by_network_empty() {
+        assert!(price_range_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn pct_change_vs_baseline_empty() {
+        let baseline = HashMap::from([(NetworkName::N1, 1.0)]);
+
+        assert!(pct_change_vs_baseline(&[], &baseline).is_empty());
+    }
+
+    #[test]
+    fn time_range_by_network_empty() {
+        assert!(time_range_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_stats_by_network_empty() {
+        assert!(aggregate_stats_by_network(vec![]).is_empty());
+        assert_eq!(0, StatsByNetwork::from(vec![]).total());
+    }
+
+    #[test]
+    fn full_stats_by_network_empty() {
+        assert!(full_stats_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn robust_average_by_network_empty() {
+        assert!(robust_average_by_network(&[], 2.0).is_empty());
+    }
+
+    #[test]
+    fn mean_ci_by_network_empty() {
+        assert!(mean_ci_by_network(&[], 1.96).is_empty());
+    }
+
+    #[test]
+    fn ewma_by_network_empty() {
+        assert!(ewma_by_network(&[], Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn mode_price_by_network_empty() {
+        assert!(mode_price_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn median_price_by_network_empty() {
+        assert!(median_price_by_network(vec![]).is_empty());
+    }
+
+    #[test]
+    fn vwap_by_network_empty() {
+        assert!(vwap_by_network(vec![]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_until_all_seen_empty() {
+        assert_eq!(
+            (HashMap::new(), false),
+            aggregate_until_all_seen(vec![], &[NetworkName::N1])
+        );
+        assert_eq!(
+            (HashMap::new(), true),
+            aggregate_until_all_seen(vec![], &[])
+        );
+    }
+
+    #[test]
+    fn aggregate_collecting_errors_empty() {
+        assert_eq!(
+            (HashMap::new(), vec![]),
+            aggregate_collecting_errors(vec![])
+        );
+    }
+
+    #[test]
+    fn weighted_median_by_network_empty() {
+        assert!(weighted_median_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn weighted_variance_by_network_empty() {
+        assert!(weighted_variance_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn percentile_by_network_empty() {
+        assert_eq!(Ok(HashMap::new()), percentile_by_network(&[], 50.0));
+    }
+
+    #[test]
+    fn price_histogram_by_network_empty() {
+        assert_eq!(Ok(HashMap::new()), price_histogram_by_network(&[], 1.0));
+    }
+
+    #[test]
+    fn geometric_mean_by_network_empty() {
+        assert_eq!(Ok(HashMap::new()), geometric_mean_by_network(&[]));
+    }
+
+    #[test]
+    fn harmonic_mean_by_network_empty() {
+        assert_eq!(Ok(HashMap::new()), harmonic_mean_by_network(&[]));
+    }
+
+    #[test]
+    fn avg_spread_by_network_empty() {
+        assert_eq!(Ok(HashMap::new()), avg_spread_by_network(&[]));
+    }
+
+    #[test]
+    fn count_by_network_empty() {
+        assert!(count_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn distinct_symbols_by_network_empty() {
+        assert!(distinct_symbols_by_network(&[]).is_empty());
+    }
+
+    #[test]
+    fn aggregate_by_network_and_symbol_empty() {
+        assert!(aggregate_by_network_and_symbol(vec![]).is_empty());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn aggregate_by_network_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.2),
+            Ticker::new("S1", 0.3),
+            Ticker::new("S2", 0.4),
+            Ticker::new("S2", 0.5),
+            Ticker::new("S2", 0.6),
+            Ticker::new("s3", 0.7),
+            Ticker::new("s3", 0.8),
+        ];
+        let result = aggregate_by_network(tickers);
+
+        assert_eq!(3, result[&NetworkName::N1].0);
+        assert_eq!(3, result[&NetworkName::N2].0);
+        assert_eq!(2, result[&NetworkName::N3].0);
+
+        assert_float_absolute_eq!(0.2, result[&NetworkName::N1].1, EPSILON);
+        assert_float_absolute_eq!(0.5, result[&NetworkName::N2].1, EPSILON);
+        assert_float_absolute_eq!(0.75, result[&NetworkName::N3].1, EPSILON);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn aggregate_by_network_large_batch_precision() {
+        let tickers: Vec<_> = ["S1", "S2", "s3"]
+            .iter()
+            .cycle()
+            .take(100_000)
+            .map(|symbol| Ticker::new(*symbol, 0.001))
+            .collect();
+        let result = aggregate_by_network(tickers);
+
+        for (_, avg) in result.values() {
+            assert_float_absolute_eq!(0.001, *avg, EPSILON);
+        }
+    }
+
+    #[test]
+    fn price_range_by_network_correct() {
+        let tickers = [
+            Ticker::new("S1", 3.0),
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 2.0),
+            Ticker::new("S2", 4.0),
+        ];
+
+        assert_eq!(
+            HashMap::from([(NetworkName::N1, 2.0), (NetworkName::N2, 0.0)]),
+            price_range_by_network(&tickers)
+        );
+    }
+
+    #[test]
+    fn full_stats_by_network_matches_individual_functions() {
+        let tickers = vec![
+            Ticker::new("S1", 0.3),
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.2),
+            Ticker::new("S2", 0.4),
+            Ticker::new("s3", 2.0),
+            Ticker::new("s3", 6.0),
+        ];
+        let result = full_stats_by_network(&tickers);
+        let counts = count_by_network(&tickers);
+        let sums = sum_by_network(&tickers);
+        let averages = aggregate_by_network_ref(&tickers);
+        let stats = aggregate_stats_by_network(tickers.clone());
+
+        assert_eq!(3, result.len());
+        for (name, full) in &result {
+            assert_eq!(counts[name], full.count);
+            assert_eq!(sums[name], full.sum);
+            assert_float_absolute_eq!(averages[name].1, full.mean as Price, EPSILON);
+            assert_eq!(stats[name].min_price, full.min);
+            assert_eq!(stats[name].max_price, full.max);
+            assert_float_absolute_eq!(stats[name].stddev_price, full.stddev as Price, EPSILON);
+            assert_float_absolute_eq!(full.stddev * full.stddev, full.variance, 1e-12);
+        }
+        assert_eq!(4.0, result[&NetworkName::N3].variance);
+
+        // the sum is compensated, a naive one would lose the small price entirely
+        let tickers = [
+            Ticker::new("S1", 1e8),
+            Ticker::new("S1", 1e-8),
+            Ticker::new("S1", -1e8),
+        ];
+        let sum = full_stats_by_network(&tickers)[&NetworkName::N1].sum;
+        assert_eq!(sum_by_network(&tickers)[&NetworkName::N1], sum);
+        assert_eq!(f64::from(1e-8 as Price), sum);
+    }
+
+    #[test]
+    fn aggregate_stats_by_network_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 0.3),
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.2),
+            Ticker::new("S2", 0.4),
+        ];
+        let result = aggregate_stats_by_network(tickers);
+
+        let n1 = &result[&NetworkName::N1];
+        assert_eq!(3, n1.count);
+        assert_float_absolute_eq!(0.2, n1.avg_price, EPSILON);
+        assert_eq!(0.1, n1.min_price);
+        assert_eq!(0.3, n1.max_price);
+        assert_float_absolute_eq!(0.081_649_66, n1.stddev_price, EPSILON);
+        assert_eq!(0.3, n1.first_price);
+        assert_eq!(0.2, n1.last_price);
+        assert_float_absolute_eq!(0.6, n1.sum, 1e-7);
+        assert_float_absolute_eq!(0.2, n1.avg().unwrap(), EPSILON);
+
+        assert_eq!(
+            NetworkStats {
+                count: 1,
+                sum: f64::from(0.4 as Price),
+                avg_price: 0.4,
+                min_price: 0.4,
+                max_price: 0.4,
+                stddev_price: 0.0,
+                first_price: 0.4,
+                last_price: 0.4,
+            },
+            result[&NetworkName::N2]
+        );
+    }
+
+    #[test]
+    fn robust_average_by_network_rejects_outliers() {
+        let mut tickers: Vec<_> = (0..9).map(|_| Ticker::new("S1", 1.0)).collect();
+        tickers.push(Ticker::new("S1", 1000.0));
+        tickers.push(Ticker::new("S2", 2.0));
+        let result = robust_average_by_network(&tickers, 2.0);
+
+        assert_eq!(1.0, result[&NetworkName::N1]);
+        assert_eq!(2.0, result[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn mean_ci_by_network_bounds() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 2.0),
+            Ticker::new("S1", 3.0),
+            Ticker::new("S2", 5.0),
+        ];
+        let result = mean_ci_by_network(&tickers, 1.96);
+
+        // sample stddev 1, standard error 1 / sqrt(3)
+        let (lower, upper) = result[&NetworkName::N1];
+        assert_float_absolute_eq!(0.868_393_4, lower, EPSILON);
+        assert_float_absolute_eq!(3.131_606_6, upper, EPSILON);
+        assert!(!result.contains_key(&NetworkName::N2));
+    }
+
+    #[test]
+    fn weighted_variance_by_network_uses_reliability_weights() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0).with_volume(1.0),
+            Ticker::new("S1", 2.0).with_volume(2.0),
+            Ticker::new("S1", 4.0).with_volume(1.0),
+            Ticker::new("S2", 1.0).with_volume(1.0),
+            Ticker::new("S2", 3.0).with_volume(1.0),
+            Ticker::new("s3", 1.0).with_volume(5.0),
+        ];
+        let result = weighted_variance_by_network(&tickers);
+
+        // weighted mean 2.25, 4.75 / (4 - 6 / 4)
+        assert_float_absolute_eq!(1.9, result[&NetworkName::N1], 1e-12);
+        // equal weights give the sample variance
+        assert_float_absolute_eq!(2.0, result[&NetworkName::N2], 1e-12);
+        assert!(!result.contains_key(&NetworkName::N3));
+    }
+
+    #[test]
+    fn robust_average_by_network_all_rejected() {
+        let tickers = vec![Ticker::new("S1", 1.0), Ticker::new("S1", 3.0)];
+
+        assert_eq!(
+            2.0,
+            robust_average_by_network(&tickers, 0.5)[&NetworkName::N1]
+        );
+    }
+
+    #[test]
+    fn ewma_by_network_halves_weights() {
+        let at = |secs, price| Ticker::new("S1", price).with_timestamp(Timestamp::from_secs(secs));
+        // weights 1, 1/2 and 1/4 from newest to oldest, out of order on purpose
+        let tickers = vec![at(20, 4.0), at(0, 8.0), at(10, 1.0)];
+        let result = ewma_by_network(&tickers, Duration::from_secs(10));
+
+        assert_float_absolute_eq!(
+            (4.0 + 0.5 + 2.0) / 1.75,
+            result[&NetworkName::N1],
+            EPSILON * 4.0
+        );
+    }
+
+    #[test]
+    fn pct_change_vs_baseline_correct() {
+        let tickers = [
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 2.0),
+            Ticker::new("S2", 1.5),
+            Ticker::new("s3", 4.0),
+        ];
+        let baseline = HashMap::from([(NetworkName::N1, 1.0), (NetworkName::N2, 2.0)]);
+
+        assert_eq!(
+            HashMap::from([(NetworkName::N1, 50.0), (NetworkName::N2, -25.0)]),
+            pct_change_vs_baseline(&tickers, &baseline)
+        );
+    }
+
+    #[test]
+    fn pct_change_vs_baseline_skips_zero_baseline() {
+        let tickers = [Ticker::new("S1", 1.0), Ticker::new("S2", 1.0)];
+        let baseline = HashMap::from([(NetworkName::N1, 0.0), (NetworkName::N2, -0.0)]);
+
+        assert!(pct_change_vs_baseline(&tickers, &baseline).is_empty());
+    }
+
+    #[test]
+    fn time_range_by_network_correct() {
+        let at = |symbol, secs| Ticker::new(symbol, 1.0).with_timestamp(Timestamp::from_secs(secs));
+        let tickers = [at("S1", 20), at("S1", 5), at("S1", 10), at("S2", 7)];
+        let result = time_range_by_network(&tickers);
+
+        assert_eq!(
+            (Timestamp::from_secs(5), Timestamp::from_secs(20)),
+            result[&NetworkName::N1]
+        );
+        assert_eq!(
+            (Timestamp::from_secs(7), Timestamp::from_secs(7)),
+            result[&NetworkName::N2]
+        );
+    }
+
+    #[test]
+    fn ewma_by_network_zero_half_life() {
+        let at = |secs, price| Ticker::new("S1", price).with_timestamp(Timestamp::from_secs(secs));
+        let tickers = vec![at(0, 8.0), at(5, 2.0), at(5, 4.0)];
+
+        assert_eq!(
+            3.0,
+            ewma_by_network(&tickers, Duration::ZERO)[&NetworkName::N1]
+        );
+    }
+
+    #[test]
+    fn percentile_by_network_interpolates() {
+        let tickers: Vec<_> = (1..=5).map(|i| Ticker::new("S1", i as Price)).collect();
+
+        assert_eq!(
+            1.0,
+            percentile_by_network(&tickers, 0.0).unwrap()[&NetworkName::N1]
+        );
+        assert_eq!(
+            3.0,
+            percentile_by_network(&tickers, 50.0).unwrap()[&NetworkName::N1]
+        );
+        assert_eq!(
+            5.0,
+            percentile_by_network(&tickers, 100.0).unwrap()[&NetworkName::N1]
+        );
+        assert_float_absolute_eq!(
+            4.8,
+            percentile_by_network(&tickers, 95.0).unwrap()[&NetworkName::N1],
+            1e-6
+        );
+    }
+
+    #[test]
+    fn percentile_by_network_single_ticker() {
+        let tickers = vec![Ticker::new("S2", 7.0)];
+
+        for p in [0.0, 37.5, 99.0, 100.0] {
+            assert_eq!(
+                7.0,
+                percentile_by_network(&tickers, p).unwrap()[&NetworkName::N2]
+            );
+        }
+    }
+
+    #[test]
+    fn percentile_by_network_out_of_range() {
+        let tickers = vec![Ticker::new("S1", 1.0)];
+
+        assert_eq!(
+            Err(InvalidPercentileError(100.5)),
+            percentile_by_network(&tickers, 100.5)
+        );
+        assert_eq!(
+            Err(InvalidPercentileError(-1.0)),
+            percentile_by_network(&tickers, -1.0)
+        );
+        assert!(percentile_by_network(&tickers, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn median_price_by_network_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 0.3),
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.2),
+            Ticker::new("S2", 0.4),
+            Ticker::new("S2", 0.1),
+            Ticker::new("S2", 0.6),
+            Ticker::new("S2", 0.5),
+        ];
+        let result = median_price_by_network(tickers);
+
+        assert_eq!(2, result.len());
+        assert_eq!(0.2, result[&NetworkName::N1]);
+        assert_float_absolute_eq!(0.45, result[&NetworkName::N2], EPSILON);
+    }
+
+    #[test]
+    fn weighted_median_by_network_correct() {
+        let tickers = [
+            Ticker::new("S1", 3.0).with_volume(1.0),
+            Ticker::new("S1", 1.0).with_volume(1.0),
+            Ticker::new("S1", 2.0).with_volume(5.0),
+            Ticker::new("S2", 1.0).with_volume(1.0),
+            Ticker::new("S2", 4.0).with_volume(2.0),
+            Ticker::new("S2", 2.0).with_volume(0.0),
+            Ticker::new("S2", 3.0).with_volume(1.0),
+            Ticker::new("s3", 1.0),
+        ];
+        let result = weighted_median_by_network(&tickers);
+
+        assert_eq!(2, result.len());
+        assert_eq!(2.0, result[&NetworkName::N1]);
+        // the cumulative volume hits exactly half at 3.0, the zero-volume 2.0 doesn't count
+        assert_eq!(3.5, result[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn mode_price_by_network_correct() {
+        let tickers = [
+            Ticker::new("S1", 0.2),
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.2),
+            Ticker::new("S2", 0.5),
+            Ticker::new("S2", 0.4),
+            Ticker::new("s3", 0.0),
+            Ticker::new("s3", -0.0),
+            Ticker::new("s3", 0.0),
+        ];
+        let result = mode_price_by_network(&tickers);
+
+        assert_eq!(0.2, result[&NetworkName::N1]);
+        assert_eq!(0.4, result[&NetworkName::N2]);
+        assert!(result[&NetworkName::N3].is_sign_positive());
+    }
+
+    #[test]
+    fn mode_price_by_network_ties_toward_smaller_price() {
+        let tickers = [
+            Ticker::new("S1", 0.0),
+            Ticker::new("S1", -0.0),
+            Ticker::new("S1", 3.0),
+            Ticker::new("S1", 3.0),
+            Ticker::new("S1", -1.0),
+            Ticker::new("S1", -1.0),
+        ];
+
+        assert_eq!(-1.0, mode_price_by_network(&tickers)[&NetworkName::N1]);
+        assert!(mode_price_by_network(&tickers[..2])[&NetworkName::N1].is_sign_negative());
+    }
+
+    #[test]
+    fn vwap_by_network_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0).with_volume(1.0),
+            Ticker::new("S1", 2.0).with_volume(3.0),
+            Ticker::new("S2", 4.0).with_volume(2.0),
+            Ticker::new("s3", 5.0),
+        ];
+        let result = vwap_by_network(tickers);
+
+        assert_eq!(2, result.len());
+        assert_float_absolute_eq!(1.75, result[&NetworkName::N1], f64::EPSILON);
+        assert_float_absolute_eq!(4.0, result[&NetworkName::N2], f64::EPSILON);
+        assert!(!result.contains_key(&NetworkName::N3));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn ticker_deserialize() {
+        let tickers: Vec<Ticker> = serde_json::from_str(
+            r#"[{"symbol":"S1","price":0.1},{"symbol":"S2","price":0.2,"volume":3.0}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(2, tickers.len());
+        assert_eq!("S1", tickers[0].symbol);
+        assert_eq!(0.1, tickers[0].price);
+        assert_eq!(0.0, tickers[0].volume);
+        assert_eq!(3.0, tickers[1].volume);
+    }
+
+    #[test]
+    fn aggregate_sorted_correct() {
+        let tickers = vec![
+            Ticker::new("s3", 0.7),
+            Ticker::new("S2", 0.4),
+            Ticker::new("S1", 0.1),
+            Ticker::new("s3", 0.9),
+        ];
+        let result = aggregate_sorted(tickers);
+
+        assert_eq!(
+            vec![NetworkName::N1, NetworkName::N2, NetworkName::N3],
+            result.iter().map(|(name, _)| *name).collect::<Vec<_>>()
+        );
+        assert_eq!(2, result[2].1 .0);
+    }
+
+    #[test]
+    fn aggregate_filtered_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.5),
+            Ticker::new("S2", 0.2),
+            Ticker::new("foo", 0.1),
+        ];
+        let result = aggregate_filtered(tickers, |ticker| ticker.price > 0.15);
+
+        assert_eq!(2, result.len());
+        assert_eq!((1, 0.5), result[&NetworkName::N1]);
+        assert_eq!((1, 0.2), result[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn aggregate_by_network_for_kind() {
+        let tickers = || {
+            vec![
+                Ticker::new("S1", 1.0),
+                Ticker::from_quote("S1", 2.0, 4.0),
+                Ticker::new("S1", 2.0).with_kind(TickerKind::Quote),
+                Ticker::new("S2", 5.0),
+            ]
+        };
+
+        let trades = aggregate_by_network_for(TickerKind::Trade, tickers());
+        assert_eq!((1, 1.0), trades[&NetworkName::N1]);
+        assert_eq!((1, 5.0), trades[&NetworkName::N2]);
+
+        let quotes = aggregate_by_network_for(TickerKind::Quote, tickers());
+        assert_eq!((2, 2.5), quotes[&NetworkName::N1]);
+        assert!(!quotes.contains_key(&NetworkName::N2));
+    }
+
+    #[test]
+    fn top_networks_by_value_correct() {
+        let tickers = [
+            Ticker::new("S1", 2.0).with_volume(1.0),
+            Ticker::new("S2", 1.0).with_volume(3.0),
+            Ticker::new("s3", 1.0).with_volume(2.0),
+            Ticker::new("s3", 1.0).with_volume(0.0),
+            Ticker::new("S1", 5.0).with_volume(0.0),
+        ];
+
+        assert_eq!(
+            vec![(NetworkName::N2, 3.0), (NetworkName::N1, 2.0)],
+            top_networks_by_value(&tickers, 2)
+        );
+        assert_eq!(
+            vec![
+                (NetworkName::N2, 3.0),
+                (NetworkName::N1, 2.0),
+                (NetworkName::N3, 2.0)
+            ],
+            top_networks_by_value(&tickers, 5)
+        );
+        assert!(top_networks_by_value(&[Ticker::new("S1", 1.0)], 1).is_empty());
+    }
+
+    #[test]
+    fn top_networks_by_value_ties() {
+        let tickers = [
+            Ticker::new("s3", 1.0).with_volume(1.0),
+            Ticker::new("S2", 0.5).with_volume(2.0),
+            Ticker::new("S1", 2.0).with_volume(0.5),
+        ];
+        let mut reversed = tickers.clone();
+        reversed.reverse();
+
+        let expected = vec![
+            (NetworkName::N1, 1.0),
+            (NetworkName::N2, 1.0),
+            (NetworkName::N3, 1.0),
+        ];
+        assert_eq!(expected, top_networks_by_value(&tickers, 3));
+        assert_eq!(expected, top_networks_by_value(&reversed, 3));
+        assert_eq!(expected[..1], top_networks_by_value(&tickers, 1));
+    }
+
+    #[test]
+    fn top_networks_by_count_ties() {
+        let tickers = [
+            Ticker::new("s3", 0.1),
+            Ticker::new("S2", 0.1),
+            Ticker::new("S1", 0.1),
+        ];
+        let mut reversed = tickers.clone();
+        reversed.reverse();
+
+        let expected = vec![
+            (NetworkName::N1, 1),
+            (NetworkName::N2, 1),
+            (NetworkName::N3, 1),
+        ];
+        assert_eq!(expected, top_networks_by_count(tickers.clone(), 3));
+        assert_eq!(expected, top_networks_by_count(reversed, 3));
+        assert_eq!(expected[..2], top_networks_by_count(tickers, 2));
+    }
+
+    #[test]
+    fn top_networks_by_count_correct() {
+        let tickers = || {
+            vec![
+                Ticker::new("s3", 0.1),
+                Ticker::new("S2", 0.1),
+                Ticker::new("S1", 0.1),
+                Ticker::new("s3", 0.1),
+                Ticker::new("S2", 0.1),
+            ]
+        };
+
+        assert_eq!(
+            vec![(NetworkName::N2, 2), (NetworkName::N3, 2)],
+            top_networks_by_count(tickers(), 2)
+        );
+        assert_eq!(
+            vec![
+                (NetworkName::N2, 2),
+                (NetworkName::N3, 2),
+                (NetworkName::N1, 1)
+            ],
+            top_networks_by_count(tickers(), 10)
+        );
+    }
+
+    #[test]
+    fn aggregate_by_custom_key() {
+        let tickers = vec![
+            Ticker::new("BINANCE:BTC", 1.0),
+            Ticker::new("BINANCE:ETH", 2.0),
+            Ticker::new("KRAKEN:BTC", 4.0),
+        ];
+        let result = aggregate_by(tickers, |symbol| {
+            symbol.split(':').next().unwrap_or_default().to_owned()
+        });
+
+        assert_eq!(2, result.len());
+        assert_eq!((2, 1.5), result["BINANCE"]);
+        assert_eq!((1, 4.0), result["KRAKEN"]);
+    }
+
+    #[test]
+    fn try_aggregate_by_network_count_beyond_u16() {
+        let tickers = (0..70_000).map(|_| Ticker::new("S1", 0.5));
+        let result = try_aggregate_by_network(tickers).unwrap();
+
+        assert_eq!(70_000, result[&NetworkName::N1].0);
+        assert_eq!(0.5, result[&NetworkName::N1].1);
+    }
+
+    #[test]
+    fn try_aggregate_by_network_correct() {
+        let tickers = vec![Ticker::new("S1", 0.1), Ticker::new("S1", 0.3)];
+        let result = try_aggregate_by_network(tickers).unwrap();
+
+        assert_eq!(1, result.len());
+        assert_eq!(2, result[&NetworkName::N1].0);
+        assert_float_absolute_eq!(0.2, result[&NetworkName::N1].1, EPSILON);
+    }
+
+    #[test]
+    fn try_aggregate_by_network_iterator() {
+        let prices = [0.1, 0.2, 0.3];
+        let result =
+            try_aggregate_by_network(prices.iter().map(|price| Ticker::new("S2", *price))).unwrap();
+
+        assert_eq!(3, result[&NetworkName::N2].0);
+        assert_float_absolute_eq!(0.2, result[&NetworkName::N2].1, EPSILON);
+    }
+
+    #[test]
+    fn try_aggregate_by_network_order_independent() {
+        // prices over many orders of magnitude, where a plain `f32` sum depends on the order
+        let mut tickers: Vec<_> = (0..1000u32)
+            .map(|i| {
+                let symbol = ["S1", "S2", "s3"][i as usize % 3];
+                Ticker::new(
+                    symbol,
+                    (i % 17) as Price * (10.0 as Price).powi(i as i32 % 9 - 4),
+                )
+            })
+            .collect();
+        let expected = try_aggregate_by_network(tickers.clone()).unwrap();
+
+        // a deterministic Fisher-Yates shuffle with a linear congruential generator
+        let mut state = 0x2545_f491_u64;
+        for round in 0..10 {
+            for i in (1..tickers.len()).rev() {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                tickers.swap(i, (state >> 33) as usize % (i + 1));
+            }
+            let result = try_aggregate_by_network(tickers.clone()).unwrap();
+
+            assert_eq!(expected, result, "shuffle {round}");
+        }
+        tickers.reverse();
+        assert_eq!(expected, try_aggregate_by_network(tickers).unwrap());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn aggregate_by_network_ref_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.3),
+            Ticker::new("s3", 0.7),
+        ];
+        let result = aggregate_by_network_ref(&tickers);
+
+        assert_eq!(2, result[&NetworkName::N1].0);
+        assert_float_absolute_eq!(0.2, result[&NetworkName::N1].1, EPSILON);
+        assert_eq!(aggregate_by_network(tickers), result);
+    }
+
+    #[test]
+    fn try_aggregate_by_network_unknown_symbol() {
+        let tickers = vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("foo", 0.2),
+            Ticker::new("bar", 0.3),
+        ];
+
+        assert_eq!(
+            Err(AggregateError::InvalidTicker {
+                index: 1,
+                reason: Box::new(AggregateError::UnknownSymbol(String::from("foo"))),
+            }),
+            try_aggregate_by_network(tickers)
+        );
+    }
+
+    #[test]
+    fn try_aggregate_by_network_reports_index() {
+        let tickers = vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("S2", 0.2),
+            Ticker::new("s3", 0.3),
+            Ticker::new("S1", Price::NAN),
+            Ticker::new("foo", 0.5),
+        ];
+        let err = try_aggregate_by_network(tickers).unwrap_err();
+
+        assert!(matches!(
+            err,
+            AggregateError::InvalidTicker { index: 3, .. }
+        ));
+        assert!(
+            matches!(err.reason(), AggregateError::InvalidPrice { symbol, .. } if symbol == "S1")
+        );
+        assert!(core::error::Error::source(&err).is_some());
+        assert_eq!(
+            "ticker 3: invalid price NaN for symbol `S1`",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn aggregate_until_all_seen_stops_early() {
+        let mut tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 3.0),
+            Ticker::new("S2", 2.0),
+            Ticker::new("S1", 5.0),
+            Ticker::new("s3", 4.0),
+        ]
+        .into_iter();
+        let expected = [NetworkName::N2, NetworkName::N1, NetworkName::N2];
+        let (result, all_seen) = aggregate_until_all_seen(tickers.by_ref(), &expected);
+
+        assert!(all_seen);
+        assert_eq!(
+            HashMap::from([(NetworkName::N1, (2, 2.0)), (NetworkName::N2, (1, 2.0))]),
+            result
+        );
+        assert_eq!(2, tickers.len());
+
+        let (result, all_seen) = aggregate_until_all_seen(tickers, &[NetworkName::N2]);
+        assert!(!all_seen);
+        assert_eq!(2, result.len());
+    }
+
+    #[test]
+    fn aggregate_collecting_errors_keeps_valid_tickers() {
+        let tickers = vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("foo", 0.2),
+            Ticker::new("S1", 0.3),
+            Ticker::new("S2", Price::INFINITY),
+            Ticker::new("S2", 0.4),
+        ];
+        let (result, errors) = aggregate_collecting_errors(tickers);
+
+        assert_eq!(2, result.len());
+        assert_eq!(2, result[&NetworkName::N1].0);
+        assert_float_absolute_eq!(0.2, result[&NetworkName::N1].1, EPSILON);
+        assert_eq!((1, 0.4), result[&NetworkName::N2]);
+        assert_eq!(
+            vec![
+                (1, AggregateError::UnknownSymbol(String::from("foo"))),
+                (
+                    3,
+                    AggregateError::InvalidPrice {
+                        symbol: String::from("S2"),
+                        price: Price::INFINITY
+                    }
+                ),
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn try_aggregate_by_network_non_finite_price() {
+        for price in [Price::NAN, Price::INFINITY, Price::NEG_INFINITY] {
+            let tickers = vec![Ticker::new("S1", 0.1), Ticker::new("S2", price)];
+
+            assert!(matches!(
+                try_aggregate_by_network(tickers).unwrap_err().reason(),
+                AggregateError::InvalidPrice { symbol, price: p }
+                    if symbol == "S2" && p.to_bits() == price.to_bits()
+            ));
+        }
+    }
+
+    #[test]
+    fn try_aggregate_by_network_with_negative_price() {
+        let tickers = || vec![Ticker::new("S1", 0.1), Ticker::new("S1", -0.3)];
+
+        let result = try_aggregate_by_network(tickers()).unwrap();
+        assert_float_absolute_eq!(-0.1, result[&NetworkName::N1].1, EPSILON);
+
+        let validation = PriceValidation {
+            negative_prices: NegativePricePolicy::Reject,
+            ..Default::default()
+        };
+        assert_eq!(
+            &AggregateError::InvalidPrice {
+                symbol: String::from("S1"),
+                price: -0.3,
+            },
+            try_aggregate_by_network_with(tickers(), validation)
+                .unwrap_err()
+                .reason()
+        );
+
+        let validation = PriceValidation {
+            negative_prices: NegativePricePolicy::Skip,
+            ..Default::default()
+        };
+        let result = try_aggregate_by_network_with(tickers(), validation).unwrap();
+        assert_eq!((1, 0.1), result[&NetworkName::N1]);
+    }
+
+    #[test]
+    fn aggregate_error_display() {
+        assert_eq!(
+            "unknown symbol `foo`",
+            AggregateError::UnknownSymbol(String::from("foo")).to_string()
+        );
+        assert_eq!(
+            "invalid price NaN for symbol `S1`",
+            AggregateError::InvalidPrice {
+                symbol: String::from("S1"),
+                price: Price::NAN,
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "ticker count overflow for network S2",
+            AggregateError::CountOverflow(NetworkName::N2).to_string()
+        );
+        assert_eq!(
+            "ticker count underflow for network S1",
+            AggregateError::CountUnderflow(NetworkName::N1).to_string()
+        );
+    }
+
+    #[test]
+    fn aggregate_error_from_parse_error() {
+        fn parse(symbol: &str) -> Result<NetworkName, AggregateError> {
+            Ok(symbol.parse::<NetworkName>()?)
+        }
+
+        assert_eq!(
+            Err(AggregateError::UnknownSymbol(String::from("foo"))),
+            parse("foo")
+        );
+    }
+
+    #[test]
+    fn network_name_from_str() {
+        assert_eq!(Ok(NetworkName::N1), "S1".parse());
+        assert_eq!(Ok(NetworkName::N2), "S2".parse());
+        assert_eq!(Ok(NetworkName::N3), "s3".parse());
+        assert_eq!(
+            Err(ParseNetworkNameError {
+                symbol: String::from("foo"),
+                did_you_mean: None,
+            }),
+            "foo".parse::<NetworkName>()
+        );
+    }
+
+    #[test]
+    fn network_name_from_str_suggestion() {
+        let err = "S4".parse::<NetworkName>().unwrap_err();
+        assert_eq!(Some("S1"), err.did_you_mean);
+        assert_eq!(
+            "unknown network symbol `S4`, did you mean `S1`?",
+            err.to_string()
+        );
+
+        let suggestion = |symbol: &str| symbol.parse::<NetworkName>().unwrap_err().did_you_mean;
+        assert_eq!(Some("s3"), suggestion("s4"));
+        assert_eq!(Some("S2"), suggestion("S22"));
+        assert_eq!(Some("S1"), suggestion("S"));
+        assert_eq!(None, suggestion(""));
+        assert_eq!(None, suggestion("N9"));
+        assert_eq!(None, suggestion("BTC"));
+    }
+
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(0, levenshtein("S1", "S1"));
+        assert_eq!(1, levenshtein("S1", "S2"));
+        assert_eq!(2, levenshtein("", "S1"));
+        assert_eq!(3, levenshtein("kitten", "sitting"));
+        assert_eq!(1, levenshtein("ä", "a"));
+    }
+
+    #[test]
+    fn known_symbols_correct() {
+        assert_eq!(
+            vec![
+                ("S1", NetworkName::N1),
+                ("S2", NetworkName::N2),
+                ("WETH", NetworkName::N2),
+                ("s3", NetworkName::N3)
+            ],
+            known_symbols().collect::<Vec<_>>()
+        );
+        for (symbol, name) in known_symbols() {
+            assert_eq!(Some(name), lookup_symbol(symbol));
+        }
+    }
+
+    #[test]
+    fn lookup_symbol_correct() {
+        assert_eq!(Some(NetworkName::N1), lookup_symbol("S1"));
+        assert_eq!(Some(NetworkName::N2), lookup_symbol("S2"));
+        assert_eq!(Some(NetworkName::N3), lookup_symbol("s3"));
+        assert_eq!(None, lookup_symbol("S3"));
+    }
+
+    #[test]
+    fn network_name_symbol() {
+        assert_eq!("S1", NetworkName::N1.symbol());
+        assert_eq!("S2", NetworkName::N2.symbol());
+        assert_eq!("s3", NetworkName::N3.symbol());
+    }
+
+    #[test]
+    fn network_name_display() {
+        assert_eq!("S1", NetworkName::N1.to_string());
+        assert_eq!("s3", format!("{}", NetworkName::N3));
+        assert_eq!("N3", format!("{:?}", NetworkName::N3));
+
+        for name in [NetworkName::N1, NetworkName::N2, NetworkName::N3] {
+            assert_eq!(Ok(name), name.to_string().parse());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn network_name_serde_round_trip() {
+        for name in [NetworkName::N1, NetworkName::N2, NetworkName::N3] {
+            let json = serde_json::to_string(&name).unwrap();
+            assert_eq!(format!("\"{}\"", name.symbol()), json);
+            assert_eq!(name, serde_json::from_str::<NetworkName>(&json).unwrap());
+        }
+
+        assert!(serde_json::from_str::<NetworkName>(r#""N1""#).is_err());
+    }
+
+    #[test]
+    fn name_from_symbol_correct() {
+        let mut cache = HashMap::new();
+
+        assert_eq!(NetworkName::N1, name_from_symbol("S1", &mut cache));
+        assert_eq!(NetworkName::N2, name_from_symbol("S2", &mut cache));
+        assert_eq!(NetworkName::N3, name_from_symbol("s3", &mut cache));
+    }
+
+    #[test]
+    fn name_from_symbol_fills_cache() {
+        let mut cache = HashMap::new();
+
+        assert_eq!(NetworkName::N1, name_from_symbol("S1", &mut cache));
+        assert_eq!(Some(&NetworkName::N1), cache.get("S1"));
+        assert_eq!(NetworkName::N1, name_from_symbol("S1", &mut cache));
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn symbol_cache_resolve() {
+        let mut cache = SymbolCache::new();
+        assert!(cache.is_empty());
+
+        // the cache outlives the strings it was queried with
+        for symbol in [String::from("S1"), String::from(" S1"), String::from("foo")] {
+            cache.resolve(&symbol);
+        }
+
+        assert_eq!(Some(NetworkName::N1), cache.resolve("S1"));
+        assert_eq!(None, cache.resolve("foo"));
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn symbol_cache_stats() {
+        let mut cache = SymbolCache::with_stats();
+        for symbol in ["S1", "S1", " S1", "S2", "foo", "foo"] {
+            cache.resolve(symbol);
+        }
+
+        assert_eq!(
+            Some(LookupStats {
+                hits: 2,
+                misses: 2,
+                unknown: 2
+            }),
+            cache.stats()
+        );
+        assert_eq!(None, SymbolCache::new().stats());
+    }
+
+    #[test]
+    fn try_aggregate_by_network_cached_counts_lookups() {
+        let mut cache = SymbolCache::with_stats();
+        let tickers = || {
+            vec![
+                Ticker::new("S1", 1.0),
+                Ticker::new("S1", 3.0),
+                Ticker::new("WETH", 2.0),
+            ]
+        };
+
+        assert_eq!(
+            try_aggregate_by_network(tickers()),
+            try_aggregate_by_network_cached(tickers(), &mut cache)
+        );
+        assert_eq!(
+            Some(LookupStats {
+                hits: 1,
+                misses: 2,
+                unknown: 0
+            }),
+            cache.stats()
+        );
+
+        let err = try_aggregate_by_network_cached(
+            vec![Ticker::new("S1", 1.0), Ticker::new("foo", 1.0)],
+            &mut cache,
+        );
+        assert!(err.is_err());
+        assert_eq!(
+            Some(LookupStats {
+                hits: 2,
+                misses: 2,
+                unknown: 1
+            }),
+            cache.stats()
+        );
+    }
+
+    #[test]
+    fn try_name_from_symbol_correct() {
+        let mut cache = HashMap::new();
+
+        assert_eq!(
+            Some(NetworkName::N1),
+            try_name_from_symbol("S1", &mut cache)
+        );
+        assert_eq!(
+            Some(NetworkName::N1),
+            try_name_from_symbol("S1", &mut cache)
+        );
+        assert_eq!(None, try_name_from_symbol("foo", &mut cache));
+        assert!(!cache.contains_key("foo"));
+    }
+
+    #[test]
+    fn classify_all_correct() {
+        let (known, unknown) = classify_all(&["S1", "BTC", " S2", "S1", "ETH", "BTC"]);
+
+        assert_eq!(
+            HashMap::from([("S1", NetworkName::N1), (" S2", NetworkName::N2)]),
+            known
+        );
+        assert_eq!(vec!["BTC", "ETH"], unknown);
+    }
+
+    #[test]
+    fn classify_all_empty() {
+        let (known, unknown) = classify_all(&[]);
+
+        assert!(known.is_empty());
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn aggregate_by_network_and_symbol_correct() {
+        let tickers = || {
+            vec![
+                Ticker::new("S1", 1.0),
+                Ticker::new("S1", 3.0),
+                Ticker::new("S2", 4.0),
+            ]
+        };
+        let result = aggregate_by_network_and_symbol(tickers());
+
+        assert_eq!(2, result.len());
+        assert_eq!((2, 2.0), result[&NetworkName::N1]["S1"]);
+        assert_eq!((1, 4.0), result[&NetworkName::N2]["S2"]);
+        assert!(result
+            .values()
+            .flat_map(HashMap::keys)
+            .all(|symbol| matches!(symbol, Cow::Borrowed(_))));
+
+        for (name, (count, _)) in aggregate_sorted(tickers()) {
+            let inner: u32 = result[&name].values().map(|(count, _)| count).sum();
+            assert_eq!(count, inner);
+        }
+    }
+
+    #[test]
+    fn price_histogram_by_network_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 1.5),
+            Ticker::new("S1", 4.0),
+            Ticker::new("S2", 2.0),
+            Ticker::new("S2", Price::NAN),
+        ];
+        let result = price_histogram_by_network(&tickers, 1.0).unwrap();
+
+        assert_eq!(
+            vec![(1.0, 2), (2.0, 0), (3.0, 0), (4.0, 1)],
+            result[&NetworkName::N1]
+        );
+        assert_eq!(vec![(2.0, 1)], result[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn price_histogram_by_network_invalid_width() {
+        let tickers = vec![Ticker::new("S1", 1.0)];
+
+        for width in [0.0, -1.0, Price::INFINITY] {
+            assert_eq!(
+                Err(InvalidBucketWidthError(width)),
+                price_histogram_by_network(&tickers, width)
+            );
+        }
+        assert!(price_histogram_by_network(&tickers, Price::NAN).is_err());
+    }
 
-fn test(tickers: Vec<Ticker>) -> HashMap<NetworkName, (u16, f32)> {
-    let mut symbols_cache = HashMap::new();
+    #[test]
+    fn geometric_mean_by_network_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 4.0),
+            Ticker::new("S2", 2.0),
+            Ticker::new("S2", 8.0),
+        ];
+        let result = geometric_mean_by_network(&tickers).unwrap();
 
-    tickers
-        .into_iter()
-        .fold(HashMap::<NetworkName, (u16, f32)>::new(), |mut res, val| {
-            let name = name_from_symbol(&val.symbol, &mut symbols_cache);
-            let entry = res.entry(name).or_insert_with(Default::default);
-            entry.0 += 1;
-            entry.1 += val.price;
-            res
-        })
-        .into_iter()
-        .map(|(key, (count, price))| {
-            let value = (count, price / count as f32);
-            (key, value)
-        })
-        .collect()
-}
+        assert_float_absolute_eq!(2.0, result[&NetworkName::N1], EPSILON);
+        assert_float_absolute_eq!(4.0, result[&NetworkName::N2], 4.0 * EPSILON);
+    }
 
-fn name_from_symbol(symbol: &str, cache: &mut HashMap<&str, NetworkName>) -> NetworkName {
-    // in a real-world application we'd rather use `FromStr` implementation for `NetworkName`. Or a
-    // `HashMap` with pre-filled values. But per requirements we need to use the slice and show, how
-    // to optimize it.
+    #[test]
+    fn geometric_mean_by_network_invalid_price() {
+        for price in [0.0, -1.0, Price::NAN, Price::INFINITY] {
+            let tickers = vec![Ticker::new("S1", 1.0), Ticker::new("S1", price)];
 
-    // this is not very rusty, but the most optimized one, because the keys inside TRACK are
-    // 'static. With methods like `.entry(...).insert_...` we couldn't just use keys from TRACK, as
-    // we would need to clone the argument (`symbol`).
-    if !cache.contains_key(symbol) {
-        let (key, value) = TRACK
+            assert!(matches!(
+                geometric_mean_by_network(&tickers),
+                Err(AggregateError::InvalidPrice { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn aggregate_lenient_unknown_bucket() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("BTC", 2.0),
+            Ticker::new("ETH", 4.0),
+            Ticker::new("S2", 1.0),
+        ];
+        let result = aggregate_lenient(tickers);
+
+        assert_eq!(3, result.len());
+        assert_eq!((1, 1.0), result[&NetworkBucket::Known(NetworkName::N1)]);
+        assert_eq!((1, 1.0), result[&NetworkBucket::Known(NetworkName::N2)]);
+        assert_eq!((2, 3.0), result[&NetworkBucket::Unknown]);
+    }
+
+    #[test]
+    fn aggregate_lenient_with_report_counts_unknown_symbols() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("BTC", 2.0),
+            Ticker::new("BTC", 4.0),
+            Ticker::new("ETH", 4.0),
+        ];
+        let (result, unknown) = aggregate_lenient_with_report(tickers);
+
+        assert_eq!(HashMap::from([(NetworkName::N1, (1, 1.0))]), result);
+        assert_eq!(
+            HashMap::from([(String::from("BTC"), 2), (String::from("ETH"), 1)]),
+            unknown
+        );
+    }
+
+    #[test]
+    fn aggregate_lenient_with_report_all_known() {
+        let (result, unknown) = aggregate_lenient_with_report(vec![Ticker::new("S2", 1.0)]);
+
+        assert_eq!(1, result.len());
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn aggregate_ticker_refs_matches_owned() {
+        let buffer = String::from("S1 S2 S1 s3");
+        let tickers: Vec<_> = buffer
+            .split(' ')
+            .zip([0.1, 0.2, 0.3, 0.4])
+            .map(|(symbol, price)| TickerRef { symbol, price })
+            .collect();
+        let owned: Vec<_> = tickers
             .iter()
-            .find(|(sym, _)| *sym == symbol)
-            .expect("symbol is valid");
-        cache.insert(key, *value);
+            .map(|ticker| Ticker::new(ticker.symbol, ticker.price))
+            .collect();
+
+        assert_eq!(
+            aggregate_by_network_ref(&owned),
+            aggregate_ticker_refs(&tickers)
+        );
+        assert_eq!(tickers[0], TickerRef::from(&owned[0]));
     }
 
-    cache[symbol]
-}
+    #[test]
+    fn stats_by_network_collect() {
+        let tickers = || vec![Ticker::new("S1", 0.1), Ticker::new("S1", 0.3)];
+        let stats: StatsByNetwork = tickers().into_iter().collect();
 
-struct Ticker {
-    symbol: String,
-    price: f32,
-}
+        assert_eq!(1, stats.len());
+        assert_eq!(2, stats[&NetworkName::N1].count);
+        assert_eq!(aggregate_stats_by_network(tickers()), stats.into_inner());
+    }
 
-#[cfg(test)]
-mod test {
-    use std::time::Instant;
+    #[test]
+    fn approx_eq_tolerance() {
+        assert!(approx_eq(0.1 + 0.2, 0.3, PRICE_TOLERANCE));
+        assert!(!approx_eq(0.1, 0.2, PRICE_TOLERANCE));
+        assert!(approx_eq(Price::INFINITY, Price::INFINITY, 0.0));
+        assert!(!approx_eq(Price::NAN, Price::NAN, 1.0));
+    }
 
-    use super::*;
-    use assert_float_eq::{
-        afe_abs, afe_absolute_error_msg, afe_is_absolute_eq, assert_float_absolute_eq,
-    };
+    #[test]
+    fn network_stats_avg_empty() {
+        let stats = NetworkStats {
+            count: 0,
+            sum: 0.0,
+            avg_price: 0.0,
+            min_price: 0.0,
+            max_price: 0.0,
+            stddev_price: 0.0,
+            first_price: 0.0,
+            last_price: 0.0,
+        };
+
+        assert_eq!(None, stats.avg());
+    }
+
+    #[test]
+    fn network_stats_approx_eq() {
+        let stats = aggregate_stats_by_network(vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.2),
+            Ticker::new("S1", 1000.3),
+        ])
+        .remove(&NetworkName::N1)
+        .unwrap();
+        let expected = NetworkStats {
+            count: 3,
+            sum: 1000.6,
+            avg_price: 333.533_33,
+            min_price: 0.1,
+            max_price: 1000.3,
+            stddev_price: 471.475_2,
+            first_price: 0.1,
+            last_price: 1000.3,
+        };
+
+        assert!(stats.approx_eq(&expected));
+        assert!(!stats.approx_eq(&NetworkStats {
+            count: 4,
+            ..expected.clone()
+        }));
+        assert!(!stats.approx_eq(&NetworkStats {
+            avg_price: 333.6,
+            ..expected
+        }));
+    }
+
+    #[test]
+    fn format_table_aligned() {
+        let result = aggregate_stats_by_network(vec![
+            Ticker::new("S2", 0.4),
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.3),
+            Ticker::new("s3", 1234.5),
+        ]);
+
+        assert_eq!(
+            "network   count          avg\n\
+             Mainnet       2     0.200000\n\
+             Optimism      1     0.400000\n\
+             Polygon       1  1234.500000\n",
+            format_table(&result)
+        );
+    }
+
+    #[test]
+    fn format_table_empty() {
+        assert_eq!("network  count  avg\n", format_table(&HashMap::new()));
+    }
+
+    #[test]
+    fn stats_by_network_iter() {
+        let stats = StatsByNetwork::from(vec![Ticker::new("S1", 0.1), Ticker::new("S2", 0.2)]);
+
+        let mut borrowed: Vec<_> = stats
+            .iter()
+            .map(|(name, stats)| (name, stats.count))
+            .collect();
+        borrowed.sort_unstable();
+        assert_eq!(vec![(NetworkName::N1, 1), (NetworkName::N2, 1)], borrowed);
+
+        let mut owned: Vec<_> = stats
+            .into_iter()
+            .map(|(name, stats)| (name, stats.count))
+            .collect();
+        owned.sort_unstable();
+        assert_eq!(borrowed, owned);
+    }
+
+    #[test]
+    fn stats_by_network_into_sorted() {
+        let tickers = [
+            Ticker::new("s3", 0.3),
+            Ticker::new("S1", 0.1),
+            Ticker::new("S2", 0.2),
+        ];
+        let stats = StatsByNetwork::from(&tickers[..]);
+        let expected = stats.clone().into_inner();
+        let sorted = stats.into_sorted();
+
+        assert_eq!(
+            vec![NetworkName::N1, NetworkName::N2, NetworkName::N3],
+            sorted.keys().copied().collect::<Vec<_>>()
+        );
+        assert!(sorted.iter().all(|(name, stats)| expected[name] == *stats));
+    }
+
+    #[test]
+    fn stats_by_network_total() {
+        let tickers = [
+            Ticker::new("S1", 0.1),
+            Ticker::new("S2", 0.2),
+            Ticker::new("S1", 0.3),
+        ];
+
+        assert_eq!(3, StatsByNetwork::from(&tickers[..]).total());
+        assert_eq!(0, StatsByNetwork::default().total());
+    }
+
+    #[test]
+    fn aggregate_with_callback_sees_every_ticker() {
+        let tickers = || vec![Ticker::new("S1", 0.1), Ticker::new("S2", 0.2)];
+        let mut seen = Vec::new();
+        let result = aggregate_with_callback(tickers(), |ticker, name| {
+            seen.push((ticker.symbol.clone().into_owned(), name));
+        });
+
+        assert_eq!(
+            vec![
+                (String::from("S1"), NetworkName::N1),
+                (String::from("S2"), NetworkName::N2),
+            ],
+            seen
+        );
+        assert_eq!(aggregate_by(tickers(), expect_network), result);
+    }
 
     #[test]
-    fn test_correct() {
+    fn aggregate_into_clears_out() {
+        let mut out = HashMap::from([(NetworkName::N3, (7, 7.0))]);
         let tickers = vec![
-            Ticker {
-                symbol: String::from("S1"),
-                price: 0.1,
-            },
-            Ticker {
-                symbol: String::from("S1"),
-                price: 0.2,
-            },
-            Ticker {
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.3),
+            Ticker::new("S2", 0.2),
+        ];
+        aggregate_into(&tickers, &mut out);
+
+        assert_eq!(aggregate_by_network_ref(&tickers), out);
+
+        aggregate_into(&[], &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn aggregate_chunked_reports_progress() {
+        let tickers: Vec<_> = (0..5).map(|_| Ticker::new("S1", 1.0)).collect();
+        let mut reported = Vec::new();
+        let result = aggregate_chunked(&tickers, 2, |processed| {
+            reported.push(processed);
+            true
+        });
+
+        assert_eq!(vec![2, 4, 5], reported);
+        assert_eq!((5, 1.0), result[&NetworkName::N1]);
+    }
+
+    #[test]
+    fn aggregate_chunked_stops_early() {
+        let tickers: Vec<_> = (0..5).map(|_| Ticker::new("S1", 1.0)).collect();
+        let result = aggregate_chunked(&tickers, 2, |processed| processed < 4);
+
+        assert_eq!((4, 1.0), result[&NetworkName::N1]);
+    }
+
+    #[test]
+    fn aggregate_all_matches_flattened_input() {
+        let batches = vec![
+            vec![Ticker::new("S1", 0.1), Ticker::new("S2", 0.2)],
+            vec![],
+            vec![Ticker::new("S1", 0.3), Ticker::new("s3", 0.4)],
+        ];
+        let flattened: Vec<_> = batches.iter().flatten().cloned().collect();
+
+        assert_eq!(aggregate_by_network_ref(&flattened), aggregate_all(batches));
+    }
+
+    #[test]
+    fn aggregate_with_overall_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 3.0),
+            Ticker::new("S2", 8.0),
+        ];
+        let (result, overall) = aggregate_with_overall(tickers.clone());
+
+        assert_eq!(aggregate_by_network_ref(&tickers), result);
+        assert_eq!(Some(4.0), overall);
+    }
+
+    #[test]
+    fn aggregate_btreemap_ordered() {
+        let tickers = vec![
+            Ticker::new("s3", 0.3),
+            Ticker::new("S1", 0.1),
+            Ticker::new("S2", 0.2),
+        ];
+        let result = aggregate_btreemap(tickers);
+
+        assert_eq!(
+            vec![NetworkName::N1, NetworkName::N2, NetworkName::N3],
+            result.into_keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn stats_by_network_from_tickers() {
+        let tickers = vec![Ticker::new("S1", 0.1), Ticker::new("S2", 0.3)];
+        let borrowed = StatsByNetwork::from(&tickers[..]);
+
+        assert_eq!(2, borrowed.len());
+        assert_eq!(borrowed, StatsByNetwork::from(tickers));
+    }
+
+    #[test]
+    fn ticker_clone_eq() {
+        let ticker = Ticker::new("S1", 0.1).with_volume(2.0);
+
+        assert_eq!(ticker, ticker.clone());
+        assert_ne!(ticker, Ticker::new("S1", 0.1));
+    }
+
+    #[test]
+    fn ticker_from_static_borrows_symbol() {
+        let ticker = Ticker::from_static("S1", 0.1);
+
+        assert!(matches!(ticker.symbol, Cow::Borrowed("S1")));
+        assert_eq!(Ticker::new("S1", 0.1), ticker);
+        assert_eq!(
+            aggregate_by_network_ref(&[Ticker::new("S1", 0.1)]),
+            aggregate_by_network_ref(&[ticker])
+        );
+    }
+
+    #[test]
+    fn aggregate_dedup_skips_replayed_tickers() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 3.0),
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 1.0).with_volume(2.0),
+            Ticker::new("S2", 1.0),
+        ];
+        let result = aggregate_dedup(&tickers);
+
+        assert_eq!((3, 5.0 / 3.0), result[&NetworkName::N1]);
+        assert_eq!((1, 1.0), result[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn aggregate_dedup_uses_bit_equality() {
+        let tickers = vec![Ticker::new("S1", 0.0), Ticker::new("S1", -0.0)];
+
+        assert_eq!(2, aggregate_dedup(&tickers)[&NetworkName::N1].0);
+    }
+
+    #[test]
+    fn avg_spread_by_network_correct() {
+        let tickers = vec![
+            Ticker::from_quote("S1", 1.0, 1.5),
+            Ticker::from_quote("S1", 2.0, 2.25),
+            Ticker::new("S1", 9.0),
+            Ticker::new("S2", 1.0),
+        ];
+        let result = avg_spread_by_network(&tickers).unwrap();
+
+        assert_eq!(1.25, tickers[0].price);
+        assert_eq!(HashMap::from([(NetworkName::N1, 0.375)]), result);
+    }
+
+    #[test]
+    fn avg_spread_by_network_crossed_quote() {
+        let tickers = vec![Ticker::from_quote("S1", 2.0, 1.0)];
+
+        assert_eq!(
+            Err(AggregateError::InvalidSpread {
                 symbol: String::from("S1"),
-                price: 0.3,
-            },
-            Ticker {
-                symbol: String::from("S2"),
-                price: 0.4,
-            },
-            Ticker {
-                symbol: String::from("S2"),
-                price: 0.5,
-            },
-            Ticker {
-                symbol: String::from("S2"),
-                price: 0.6,
-            },
-            Ticker {
-                symbol: String::from("s3"),
-                price: 0.7,
+                bid: 2.0,
+                ask: 1.0,
+            }),
+            avg_spread_by_network(&tickers)
+        );
+    }
+
+    #[test]
+    fn harmonic_mean_by_network_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 4.0),
+            Ticker::new("S1", 4.0),
+            Ticker::new("S2", 3.0),
+        ];
+        let result = harmonic_mean_by_network(&tickers).unwrap();
+
+        assert_eq!(2.0, result[&NetworkName::N1]);
+        assert_eq!(3.0, result[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn harmonic_mean_by_network_invalid_price() {
+        for price in [0.0, -1.0, Price::NAN] {
+            let tickers = vec![Ticker::new("S1", price)];
+
+            assert!(matches!(
+                harmonic_mean_by_network(&tickers),
+                Err(AggregateError::InvalidPrice { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn count_by_network_correct() {
+        let tickers = [
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", Price::NAN),
+            Ticker::new("s3", 3.0),
+        ];
+        let counts = count_by_network(&tickers);
+
+        assert_eq!(
+            HashMap::from([(NetworkName::N1, 2), (NetworkName::N3, 1)]),
+            counts
+        );
+        for (name, (count, _)) in aggregate_by_network_ref(&tickers) {
+            assert_eq!(count, counts[&name]);
+        }
+    }
+
+    #[test]
+    fn distinct_symbols_by_network_correct() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 2.0),
+            Ticker::new("S2", 3.0),
+        ];
+        let result = distinct_symbols_by_network(&tickers);
+
+        assert_eq!(2, result.len());
+        assert_eq!(1, result[&NetworkName::N1]);
+        assert_eq!(1, result[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn network_name_order() {
+        assert!(NetworkName::N1 < NetworkName::N2);
+        assert!(NetworkName::N2 < NetworkName::N3);
+
+        let mut names = vec![NetworkName::N3, NetworkName::N1, NetworkName::N2];
+        names.sort();
+        assert_eq!(
+            vec![NetworkName::N1, NetworkName::N2, NetworkName::N3],
+            names
+        );
+    }
+
+    #[test]
+    fn network_metadata_correct() {
+        assert_eq!(1, NetworkName::N1.chain_id());
+        assert_eq!("Mainnet", NetworkName::N1.display_name());
+
+        for name in [NetworkName::N1, NetworkName::N2, NetworkName::N3] {
+            assert_eq!(Some(name), NetworkName::from_chain_id(name.chain_id()));
+        }
+        assert_eq!(None, NetworkName::from_chain_id(0));
+
+        for (i, (name, _, _)) in METADATA.iter().enumerate() {
+            assert_eq!(i, *name as usize);
+        }
+    }
+
+    #[test]
+    fn track_is_sorted() {
+        assert!(TRACK.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[cfg(not(feature = "phf"))]
+    #[test]
+    fn packed_track_is_sorted() {
+        assert!(PACKED_TRACK.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn lookup_symbol_short_and_long() {
+        for (symbol, name) in TRACK {
+            assert_eq!(Some(*name), lookup_symbol(symbol));
+        }
+        // shares the packed representation of "S1", but not its length
+        assert_eq!(None, lookup_symbol("S1\0"));
+        assert_eq!(None, lookup_symbol("S1S1S1S1S1"));
+        assert_eq!(None, lookup_symbol(""));
+    }
+
+    #[test]
+    fn sorted_symbols_correct() {
+        let mut symbols = NetworkName::SYMBOLS.to_vec();
+        symbols.sort();
+        assert_eq!(symbols, NetworkName::SORTED_SYMBOLS);
+    }
+
+    #[test]
+    fn is_strictly_sorted_rejects_duplicates() {
+        assert!(__is_strictly_sorted(&[
+            ("S1", NetworkName::N1),
+            ("S10", NetworkName::N2)
+        ]));
+        assert!(!__is_strictly_sorted(&[
+            ("S1", NetworkName::N1),
+            ("S1", NetworkName::N2)
+        ]));
+        assert!(!__is_strictly_sorted(&[
+            ("S2", NetworkName::N1),
+            ("S1", NetworkName::N2)
+        ]));
+    }
+
+    #[cfg(feature = "phf")]
+    #[test]
+    fn symbol_table_matches_track() {
+        assert_eq!(TRACK.len(), SYMBOL_TABLE.len());
+        for (symbol, name) in TRACK {
+            assert_eq!(Some(name), SYMBOL_TABLE.get(symbol));
+        }
+    }
+
+    #[test]
+    fn aliases_for_correct() {
+        assert!(aliases_for(NetworkName::N1).is_empty());
+        assert_eq!(vec!["WETH"], aliases_for(NetworkName::N2));
+        assert!(aliases_for(NetworkName::N3).is_empty());
+
+        for (symbol, name) in TRACK {
+            assert_eq!(
+                *symbol != name.symbol(),
+                aliases_for(*name).contains(symbol)
+            );
+        }
+    }
+
+    #[test]
+    fn symbols_for_correct() {
+        assert_eq!(vec!["S1"], symbols_for(NetworkName::N1));
+        assert_eq!(vec!["S2", "WETH"], symbols_for(NetworkName::N2));
+        assert_eq!(vec!["s3"], symbols_for(NetworkName::N3));
+
+        for (symbol, name) in TRACK {
+            assert!(symbols_for(*name).contains(symbol));
+            assert_eq!(name.symbol(), symbols_for(*name)[0]);
+        }
+    }
+
+    #[test]
+    fn aggregate_by_network_with_hasher_deterministic() {
+        type Fixed = core::hash::BuildHasherDefault<std::collections::hash_map::DefaultHasher>;
+
+        let tickers = || {
+            vec![
+                Ticker::new("S1", 1.0),
+                Ticker::new("S2", 2.0),
+                Ticker::new("s3", 3.0),
+            ]
+        };
+        let first = aggregate_by_network_with_hasher::<Fixed>(tickers());
+        let second = aggregate_by_network_with_hasher::<Fixed>(tickers());
+
+        assert_eq!((1, 2.0), first[&NetworkName::N2]);
+        assert!(first.iter().eq(second.iter()));
+    }
+
+    #[test]
+    fn aggregate_rounded_ties_to_even() {
+        let tickers = vec![
+            Ticker::new("S1", 0.125),
+            Ticker::new("S2", 0.375),
+            Ticker::new("s3", 2.0),
+            Ticker::new("s3", 3.0),
+        ];
+
+        let result = aggregate_rounded(tickers.clone(), 2);
+        assert_eq!(0.12, result[&NetworkName::N1]);
+        assert_eq!(0.38, result[&NetworkName::N2]);
+        assert_eq!(2.0, aggregate_rounded(tickers.clone(), 0)[&NetworkName::N3]);
+        assert_eq!(2.5, aggregate_rounded(tickers, u32::MAX)[&NetworkName::N3]);
+    }
+
+    #[test]
+    fn aggregate_all_networks_reports_empty_networks() {
+        let tickers = vec![Ticker::new("S1", 1.0), Ticker::new("S1", 3.0)];
+        let result = aggregate_all_networks(tickers);
+
+        assert_eq!(METADATA.len(), result.len());
+        assert_eq!(
+            NetworkAverage {
+                count: 2,
+                avg_price: Some(2.0)
             },
-            Ticker {
-                symbol: String::from("s3"),
-                price: 0.8,
+            result[&NetworkName::N1]
+        );
+        for name in [NetworkName::N2, NetworkName::N3] {
+            assert_eq!(
+                NetworkAverage {
+                    count: 0,
+                    avg_price: None
+                },
+                result[&name]
+            );
+        }
+    }
+
+    #[test]
+    fn averages_filtered_empty_network() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 3.0),
+            Ticker::new("S2", 100.0),
+        ];
+        let result = averages_filtered(tickers, |ticker| ticker.price < 10.0);
+
+        assert_eq!(3, result.len());
+        assert_eq!(
+            NetworkAverage {
+                count: 2,
+                avg_price: Some(2.0)
             },
+            result[&NetworkName::N1]
+        );
+        for name in [NetworkName::N2, NetworkName::N3] {
+            assert_eq!(
+                NetworkAverage {
+                    count: 0,
+                    avg_price: None
+                },
+                result[&name]
+            );
+        }
+    }
+
+    #[test]
+    fn sum_by_network_keeps_the_sums() {
+        let tickers = [
+            Ticker::new("S1", 1.0),
+            Ticker::new("S2", 0.5),
+            Ticker::new("S1", 3.0),
         ];
-        let result = test(tickers);
+        let result = sum_by_network(&tickers);
 
-        assert_eq!(3, result[&NetworkName::N1].0);
-        assert_eq!(3, result[&NetworkName::N2].0);
-        assert_eq!(2, result[&NetworkName::N3].0);
+        assert_eq!(4.0, result[&NetworkName::N1]);
+        assert_eq!(0.5, result[&NetworkName::N2]);
+        assert!(sum_by_network(&[]).is_empty());
+    }
 
-        assert_float_absolute_eq!(0.2, result[&NetworkName::N1].1, f32::EPSILON);
-        assert_float_absolute_eq!(0.5, result[&NetworkName::N2].1, f32::EPSILON);
-        assert_float_absolute_eq!(0.75, result[&NetworkName::N3].1, f32::EPSILON);
+    #[test]
+    fn aggregate_grouped_by_price_band() {
+        let tickers = vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S2", 3.0),
+            Ticker::new("S1", 10.0),
+            Ticker::new("s3", 30.0),
+        ];
+        let result = aggregate_grouped(&tickers, |ticker| ticker.price >= 5.0);
+
+        assert_eq!((2, 2.0), result[&false]);
+        assert_eq!((2, 20.0), result[&true]);
     }
 
     #[test]
-    fn name_from_symbol_correct() {
-        let mut cache = HashMap::new();
+    fn aggregate_by_aliases() {
+        let tickers = || {
+            vec![
+                Ticker::new("S2", 2.0),
+                Ticker::new("WETH", 4.0),
+                Ticker::new("S1", 1.0),
+            ]
+        };
+        let expected = HashMap::from([(NetworkName::N1, (1, 1.0)), (NetworkName::N2, (2, 3.0))]);
 
-        assert_eq!(NetworkName::N1, name_from_symbol("S1", &mut cache));
+        #[allow(deprecated)]
+        let result = aggregate_by_network(tickers());
+        assert_eq!(expected, result);
+        assert_eq!(Ok(expected), try_aggregate_by_network(tickers()));
+
+        let mut cache = HashMap::new();
+        assert_eq!(NetworkName::N2, name_from_symbol("WETH", &mut cache));
         assert_eq!(NetworkName::N2, name_from_symbol("S2", &mut cache));
-        assert_eq!(NetworkName::N3, name_from_symbol("s3", &mut cache));
+        assert_eq!(Some(NetworkName::N2), lookup_symbol("WETH"));
+        assert_eq!("S2", NetworkName::N2.to_string());
+        assert_eq!(Ok(NetworkName::N2), "WETH".parse());
+    }
+
+    #[test]
+    fn name_from_symbol_trims_whitespace() {
+        let mut cache = HashMap::new();
+
+        assert_eq!(NetworkName::N1, name_from_symbol(" S1 ", &mut cache));
+        assert_eq!(NetworkName::N2, name_from_symbol("S2\t", &mut cache));
+        assert_eq!(NetworkName::N3, name_from_symbol_ci(" S3", &mut cache));
+
+        let mut keys: Vec<_> = cache.into_keys().collect();
+        keys.sort_unstable();
+        assert_eq!(vec!["S1", "S2", "s3"], keys);
     }
 
     #[test]
-    fn name_from_symbol_cache() {
+    fn name_from_symbol_ci_correct() {
         let mut cache = HashMap::new();
 
-        let now = Instant::now();
-        name_from_symbol("S1", &mut cache);
-        let before_cache = now.elapsed();
+        assert_eq!(NetworkName::N1, name_from_symbol_ci("s1", &mut cache));
+        assert_eq!(NetworkName::N2, name_from_symbol_ci("S2", &mut cache));
+        assert_eq!(NetworkName::N3, name_from_symbol_ci("S3", &mut cache));
+        assert_eq!(None, try_name_from_symbol_ci("s4", &mut cache));
 
-        let now = Instant::now();
-        name_from_symbol("S1", &mut cache);
-        let after_cache = now.elapsed();
+        let mut keys: Vec<_> = cache.into_keys().collect();
+        keys.sort_unstable();
+        assert_eq!(vec!["S1", "S2", "s3"], keys);
+    }
+
+    #[test]
+    fn eq_ignore_case_folding() {
+        assert!(eq_ignore_case("S1", "s1"));
+        assert!(!eq_ignore_case("S1", "S2"));
+        assert_eq!(cfg!(feature = "unicode"), eq_ignore_case("ÄΣ1", "äσ1"));
+    }
 
-        assert!(after_cache < before_cache);
+    #[test]
+    fn fold_case_matches_eq_ignore_case() {
+        let symbols = ["S1", "s1", "S2", "ÄΣ1", "äσ1", "", "İ", "i"];
+        for a in symbols {
+            for b in symbols {
+                assert_eq!(
+                    eq_ignore_case(a, b),
+                    fold_case(a) == fold_case(b),
+                    "{a} {b}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn track_entry_ci_correct() {
+        for (symbol, name) in TRACK {
+            assert_eq!(Some((*symbol, *name)), track_entry_ci(symbol));
+            assert_eq!(
+                Some((*symbol, *name)),
+                track_entry_ci(&symbol.to_ascii_uppercase())
+            );
+        }
+        assert_eq!(None, track_entry_ci("S4"));
     }
 
     #[test]
@@ -0,0 +1,180 @@
+/// Defines a network enum together with its symbols, so the variants and the symbol table can't
+/// drift apart.
+///
+/// Every variant is listed with its symbol, optionally followed by aliases separated by `|`, e.g.
+/// `N2 => "S2" | "WETH"`. The first symbol is the variant's canonical one. The macro generates:
+///
+/// - the enum itself, deriving `Clone`, `Copy`, `Debug`, `PartialEq`, `Eq`, `PartialOrd`, `Ord`
+///   and `Hash`, so the variants are ordered by declaration,
+/// - `SYMBOLS`, an associated const with every symbol, aliases included, and its variant in
+///   declaration order,
+/// - `SORTED_SYMBOLS`, the same entries sorted by symbol, ready to be binary searched. A symbol
+///   declared twice fails to compile,
+/// - `symbol(self)`, which returns the variant's canonical symbol,
+/// - [`Display`](core::fmt::Display), which renders the canonical symbol,
+/// - [`FromStr`](core::str::FromStr), which parses any of the symbols and fails with
+///   [`ParseNetworkNameError`](crate::ParseNetworkNameError), suggesting the closest symbol.
+///
+/// [`NetworkName`](crate::NetworkName) is defined with it, and other crates can use it for their
+/// own sets of networks:
+///
+/// ```
+/// bhtest::networks! {
+///     /// The chains we trade on.
+///     pub enum Chain {
+///         Ethereum => "ETH" | "WETH",
+///         Optimism => "OP",
+///     }
+/// }
+///
+/// assert_eq!("OP", Chain::Optimism.to_string());
+/// assert_eq!("ETH", Chain::Ethereum.to_string());
+/// assert_eq!(Ok(Chain::Ethereum), "ETH".parse());
+/// assert_eq!(Ok(Chain::Ethereum), "WETH".parse());
+/// assert!("BTC".parse::<Chain>().is_err());
+/// assert_eq!(
+///     &[
+///         ("ETH", Chain::Ethereum),
+///         ("WETH", Chain::Ethereum),
+///         ("OP", Chain::Optimism)
+///     ],
+///     Chain::SYMBOLS
+/// );
+/// assert_eq!(
+///     &[
+///         ("ETH", Chain::Ethereum),
+///         ("OP", Chain::Optimism),
+///         ("WETH", Chain::Ethereum)
+///     ],
+///     Chain::SORTED_SYMBOLS
+/// );
+/// ```
+#[macro_export]
+macro_rules! networks {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_attr:meta])*
+                $variant:ident => $symbol:literal $(| $alias:literal)*
+            ),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        $vis enum $name {
+            $($(#[$variant_attr])* $variant,)+
+        }
+
+        impl $name {
+            /// Every symbol, aliases included, and its network, in declaration order.
+            pub const SYMBOLS: &'static [(&'static str, Self)] = &[
+                $(($symbol, Self::$variant), $(($alias, Self::$variant),)*)+
+            ];
+
+            /// Every symbol, aliases included, and its network, sorted by symbol.
+            pub const SORTED_SYMBOLS: &'static [(&'static str, Self)] = &{
+                let mut symbols = [$(($symbol, Self::$variant), $(($alias, Self::$variant),)*)+];
+                // insertion sort, as `sort` isn't usable in const context
+                let mut i = 1;
+                while i < symbols.len() {
+                    let mut j = i;
+                    while j > 0 && $crate::__str_lt(symbols[j].0, symbols[j - 1].0) {
+                        symbols.swap(j - 1, j);
+                        j -= 1;
+                    }
+                    i += 1;
+                }
+                symbols
+            };
+
+            /// Returns the canonical symbol of the network, the first one it was declared with.
+            pub fn symbol(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $symbol,)+
+                }
+            }
+        }
+
+        // strictly sorted also means no symbol appears twice
+        const _: () = assert!(
+            $crate::__is_strictly_sorted($name::SORTED_SYMBOLS),
+            "every symbol must be declared only once"
+        );
+
+        /// Renders the canonical symbol, so that it round-trips through
+        /// [`FromStr`](core::str::FromStr).
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.pad(self.symbol())
+            }
+        }
+
+        impl ::core::str::FromStr for $name {
+            type Err = $crate::ParseNetworkNameError;
+
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                match s {
+                    $($symbol $(| $alias)* => Ok(Self::$variant),)+
+                    _ => Err($crate::ParseNetworkNameError::new(
+                        s,
+                        Self::SYMBOLS.iter().map(|(symbol, _)| *symbol),
+                    )),
+                }
+            }
+        }
+    };
+}
+
+// `networks!` for `NetworkName`, which also generates `SYMBOL_TABLE` from the same symbols under
+// the `phf` feature, as `phf_map!` can't be fed from a const slice
+macro_rules! builtin_networks {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$variant_attr:meta])*
+                $variant:ident => $symbol:literal $(| $alias:literal)*
+            ),+ $(,)?
+        }
+    ) => {
+        networks! {
+            $(#[$attr])*
+            $vis enum $name {
+                $($(#[$variant_attr])* $variant => $symbol $(| $alias)*,)+
+            }
+        }
+
+        /// `TRACK` as a perfect hash map, built at compile time.
+        #[cfg(feature = "phf")]
+        static SYMBOL_TABLE: phf::Map<&'static str, $name> = phf::phf_map! {
+            $($symbol => $name::$variant, $($alias => $name::$variant,)*)+
+        };
+    };
+}
+
+#[doc(hidden)]
+pub const fn __is_strictly_sorted<T>(symbols: &[(&str, T)]) -> bool {
+    let mut i = 1;
+    while i < symbols.len() {
+        if !__str_lt(symbols[i - 1].0, symbols[i].0) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// `a < b` for `str`, which isn't usable in const context
+#[doc(hidden)]
+pub const fn __str_lt(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] != b[i] {
+            return a[i] < b[i];
+        }
+        i += 1;
+    }
+    a.len() < b.len()
+}
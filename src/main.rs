@@ -0,0 +1,91 @@
+//! Reads `symbol,price` CSV from stdin and prints the ticker count and average price per network.
+//!
+//! Pass `--json` to print JSON instead of a table.
+
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+use bhtest::{from_csv_reader, result_to_json, try_aggregate_by_network};
+
+const USAGE: &str = "usage: bhtest [--json] < tickers.csv";
+
+fn main() -> ExitCode {
+    let mut json = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => {
+                eprintln!("unknown argument `{arg}`\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    match run(io::stdin().lock(), json) {
+        Ok(output) => {
+            print!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(input: impl Read, json: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let tickers = from_csv_reader(input)?;
+    let result = try_aggregate_by_network(tickers)?;
+
+    if json {
+        return Ok(result_to_json(&result) + "\n");
+    }
+
+    let mut rows: Vec<_> = result.into_iter().collect();
+    rows.sort_unstable_by_key(|(name, _)| *name);
+    let mut output = format!("{:<8} {:>8} {:>12}\n", "network", "count", "avg");
+    for (name, (count, avg)) in rows {
+        output += &format!("{:<8} {:>8} {:>12}\n", name, count, avg);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INPUT: &str = "symbol,price\nS2,0.5\nS1,0.1\nS1,0.3\n";
+
+    #[test]
+    fn run_table() {
+        let output = run(INPUT.as_bytes(), false).unwrap();
+
+        assert_eq!(
+            "network     count          avg\n\
+             S1              2          0.2\n\
+             S2              1          0.5\n",
+            output
+        );
+    }
+
+    #[test]
+    fn run_json() {
+        let output = run(INPUT.as_bytes(), true).unwrap();
+
+        assert_eq!(
+            "{\"S1\":{\"count\":2,\"avg\":0.2},\"S2\":{\"count\":1,\"avg\":0.5}}\n",
+            output
+        );
+    }
+
+    #[test]
+    fn run_unknown_symbol() {
+        let err = run("symbol,price\nBTC,1.0\n".as_bytes(), false).unwrap_err();
+
+        assert_eq!("ticker 0: unknown symbol `BTC`", err.to_string());
+    }
+}
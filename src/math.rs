@@ -0,0 +1,52 @@
+// float functions that `core` doesn't provide, backed by `libm` without `std`
+
+#[cfg(feature = "std")]
+pub(crate) fn sqrt(x: f64) -> f64 {
+    x.sqrt()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn sqrt(x: f64) -> f64 {
+    libm::sqrt(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn ln(x: f64) -> f64 {
+    x.ln()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn ln(x: f64) -> f64 {
+    libm::log(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn exp(x: f64) -> f64 {
+    x.exp()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn exp(x: f64) -> f64 {
+    libm::exp(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn powi(x: f64, n: i32) -> f64 {
+    x.powi(n)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn powi(x: f64, n: i32) -> f64 {
+    libm::pow(x, f64::from(n))
+}
+
+// rounds half to even, like `f64::round_ties_even`
+#[cfg(feature = "std")]
+pub(crate) fn round_ties_even(x: f64) -> f64 {
+    x.round_ties_even()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn round_ties_even(x: f64) -> f64 {
+    libm::rint(x)
+}
@@ -0,0 +1,59 @@
+use rayon::prelude::*;
+
+use crate::{
+    add_to_sums, averages, expect_network, merge_sums, HashMap, NetworkAggregate, NetworkName,
+    Ticker,
+};
+
+/// Like [`aggregate_by_network_ref`](crate::aggregate_by_network_ref), but folds the tickers on
+/// the rayon thread pool.
+///
+/// Every thread accumulates counts and price sums into its own map, and the maps are merged
+/// before dividing, so the result matches the sequential aggregation.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn par_aggregate_by_network(tickers: &[Ticker]) -> HashMap<NetworkName, NetworkAggregate> {
+    let sums = tickers
+        .par_iter()
+        .fold(HashMap::new, |mut sums, val| {
+            let name = expect_network(&val.symbol);
+            add_to_sums(&mut sums, name, val.price);
+            sums
+        })
+        .reduce(HashMap::new, merge_sums);
+
+    averages(sums)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{aggregate_by_network_ref, Price};
+
+    #[test]
+    fn par_aggregate_by_network_matches_sequential() {
+        let tickers: Vec<_> = ["S1", "S2", "s3"]
+            .iter()
+            .cycle()
+            .take(60_000)
+            .enumerate()
+            .map(|(i, symbol)| Ticker::new(*symbol, (i % 1000) as Price / 7.0))
+            .collect();
+
+        let sequential = aggregate_by_network_ref(&tickers);
+        let parallel = par_aggregate_by_network(&tickers);
+
+        assert_eq!(sequential.len(), parallel.len());
+        for (name, (count, avg)) in sequential {
+            assert_eq!(count, parallel[&name].0);
+            assert!((avg - parallel[&name].1).abs() <= f32::EPSILON as Price);
+        }
+    }
+
+    #[test]
+    fn par_aggregate_by_network_empty() {
+        assert!(par_aggregate_by_network(&[]).is_empty());
+    }
+}
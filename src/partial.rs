@@ -0,0 +1,638 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "simd")]
+use wide::{f64x4, CmpGe};
+
+use crate::{
+    add_to_sums, averages, expect_network, merge_sums, HashMap, NetworkAggregate, NetworkName,
+    Price, Ticker,
+};
+
+/// The count and price sum of a group of tickers, before the division into an average.
+///
+/// Unlike finalized averages, partial aggregates can be merged without losing anything, which
+/// makes them the right thing to combine across shards, threads or batches. Every aggregation in
+/// this crate folds into them and only finalizes at the end.
+///
+/// The sum is compensated to about twice the precision of `f64`, far more than the finalized
+/// [`Price`] keeps, so the average doesn't depend on the order the prices were added in.
+///
+/// The count is a `u64`, so merging the partial aggregates of many shards doesn't overflow it. It's
+/// only narrowed to the `u32` of a [`NetworkAggregate`] when finalized into one.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PartialAggregate {
+    count: u64,
+    // prices are summed in `f64` and only narrowed after the division, so large batches don't
+    // accumulate `f32` rounding errors
+    sum: f64,
+    // the low-order bits lost from `sum` so far, see `add`
+    compensation: f64,
+}
+
+// the largest count a `NetworkAggregate` holds, which the overflow policies guard
+const AGGREGATE_COUNT_MAX: u64 = u32::MAX as u64;
+
+impl PartialAggregate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the count would overflow.
+    pub fn add_price(&mut self, price: Price) {
+        self.count = self.count.checked_add(1).expect("count doesn't overflow");
+        self.add(f64::from(price));
+    }
+
+    /// Adds every price in `prices`, the same as calling [`add_price`](Self::add_price) for each of
+    /// them.
+    ///
+    /// With the `simd` feature the prices are summed four at a time, each lane with its own
+    /// compensation, and the lanes and the remainder are folded into `self` at the end. The sum
+    /// can differ from adding the prices one by one in the last bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the count would overflow.
+    pub fn add_prices(&mut self, prices: &[Price]) {
+        let count = u64::try_from(prices.len())
+            .ok()
+            .and_then(|len| self.count.checked_add(len))
+            .expect("count doesn't overflow");
+
+        #[cfg(feature = "simd")]
+        let prices = {
+            let chunks = prices.chunks_exact(4);
+            let remainder = chunks.remainder();
+            let (mut sum, mut compensation) = (f64x4::ZERO, f64x4::ZERO);
+            for chunk in chunks {
+                let value = f64x4::new(core::array::from_fn(|i| f64::from(chunk[i])));
+                // the same Neumaier step as in `add`, in every lane
+                let next = sum + value;
+                let larger_sum = sum.abs().cmp_ge(value.abs());
+                compensation += larger_sum.blend((sum - next) + value, (value - next) + sum);
+                sum = next;
+            }
+            for (sum, compensation) in sum.to_array().into_iter().zip(compensation.to_array()) {
+                self.add(sum);
+                self.add(compensation);
+            }
+            remainder
+        };
+
+        for price in prices {
+            self.add(f64::from(*price));
+        }
+        self.count = count;
+    }
+
+    /// Like [`add_price`](Self::add_price), but leaves `self` untouched and returns `false` if
+    /// the count would overflow the count of a [`NetworkAggregate`].
+    pub(crate) fn checked_add_price(&mut self, price: Price) -> bool {
+        if self.count >= AGGREGATE_COUNT_MAX {
+            return false;
+        }
+        self.count += 1;
+        self.add(f64::from(price));
+        true
+    }
+
+    /// Like [`add_price`](Self::add_price), but lets the count wrap around to 0 where the count of
+    /// a [`NetworkAggregate`] would overflow.
+    pub(crate) fn wrapping_add_price(&mut self, price: Price) {
+        self.count = (self.count + 1) % (AGGREGATE_COUNT_MAX + 1);
+        self.add(f64::from(price));
+    }
+
+    /// Takes `price` back out, as if it had never been added. Leaves `self` untouched and returns
+    /// `false` if no prices are left.
+    pub(crate) fn remove_price(&mut self, price: Price) -> bool {
+        let Some(count) = self.count.checked_sub(1) else {
+            return false;
+        };
+        if count == 0 {
+            // drop the rounding residue, so an emptied aggregate is the same as a fresh one
+            *self = Self::default();
+        } else {
+            self.count = count;
+            self.add(-f64::from(price));
+        }
+        true
+    }
+
+    /// Folds `other` into `self`, as if all of its prices had been added to `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the count would overflow.
+    pub fn merge(&mut self, other: &Self) {
+        self.count = self
+            .count
+            .checked_add(other.count)
+            .expect("count doesn't overflow");
+        self.add(other.sum);
+        self.add(other.compensation);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the compensated sum of the prices.
+    pub fn sum(&self) -> f64 {
+        self.sum + self.compensation
+    }
+
+    /// Returns the average price, or `None` if no prices were added.
+    ///
+    /// The division is done in `f64` and only the result is narrowed to [`Price`], so even counts
+    /// beyond `u32::MAX` don't lose precision.
+    pub fn finalize(&self) -> Option<Price> {
+        self.mean().map(|mean| mean as Price)
+    }
+
+    // the average before it's narrowed to `Price`
+    pub(crate) fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum() / self.count as f64)
+    }
+
+    // Kahan-Babuška (Neumaier) summation: the rounding error of every addition is kept in
+    // `compensation`, so sums of values many orders of magnitude apart don't depend on their order
+    fn add(&mut self, value: f64) {
+        let sum = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - sum) + value;
+        } else {
+            self.compensation += (value - sum) + self.sum;
+        }
+        self.sum = sum;
+    }
+
+    // the count narrowed to the `u32` of a `NetworkAggregate`
+    pub(crate) fn aggregate_count(&self) -> u32 {
+        u32::try_from(self.count).expect("count doesn't overflow")
+    }
+
+    // the count and average of a `NetworkAggregate`, or `None` if no prices were added
+    pub(crate) fn to_aggregate(self) -> Option<NetworkAggregate> {
+        Some((self.aggregate_count(), self.finalize()?))
+    }
+}
+
+/// Groups `tickers` by network into [`PartialAggregate`]s, to be merged with other partial
+/// results and finalized later.
+///
+/// A partial aggregate carries both the sum and the count, so callers that need the sum, the
+/// average or both get them from the same pass:
+///
+/// ```
+/// use bhtest::{partial_aggregate_by_network, NetworkName, Ticker};
+///
+/// let tickers = vec![Ticker::new("S1", 1.0), Ticker::new("S1", 2.0)];
+/// let partials = partial_aggregate_by_network(tickers);
+/// let n1 = &partials[&NetworkName::N1];
+///
+/// assert_eq!(2, n1.count());
+/// assert_eq!(3.0, n1.sum());
+/// assert_eq!(Some(1.5), n1.finalize());
+/// ```
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn partial_aggregate_by_network(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, PartialAggregate> {
+    tickers.into_iter().fold(HashMap::new(), |mut sums, val| {
+        add_to_sums(&mut sums, expect_network(&val.symbol), val.price);
+        sums
+    })
+}
+
+/// Merges two partial results, e.g. from separate shards.
+///
+/// # Panics
+///
+/// Panics if a network's count would overflow.
+pub fn merge_partial_aggregates(
+    a: HashMap<NetworkName, PartialAggregate>,
+    b: HashMap<NetworkName, PartialAggregate>,
+) -> HashMap<NetworkName, PartialAggregate> {
+    merge_sums(a, b)
+}
+
+/// Turns partial results into the count and average per network.
+///
+/// # Panics
+///
+/// Panics if a network's count doesn't fit the `u32` of a [`NetworkAggregate`].
+pub fn finalize_aggregates(
+    partials: HashMap<NetworkName, PartialAggregate>,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    averages(partials)
+}
+
+/// Merges two finalized results by weighting each average with its count.
+///
+/// This is lossy: the averages were already narrowed to [`Price`](crate::Price), so the merged
+/// average can differ from aggregating the combined input. Merge [`PartialAggregate`]s with
+/// [`merge_partial_aggregates`] instead when possible.
+///
+/// # Panics
+///
+/// Panics if a network's count would overflow.
+pub fn merge_aggregates(
+    a: HashMap<NetworkName, NetworkAggregate>,
+    b: HashMap<NetworkName, NetworkAggregate>,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    let mut merged = a;
+    for (name, (count, avg)) in b {
+        merged
+            .entry(name)
+            .and_modify(|(merged_count, merged_avg)| {
+                let total = merged_count
+                    .checked_add(count)
+                    .expect("count doesn't overflow");
+                // two empty groups have no average to weight, and dividing would give `NaN`
+                if total > 0 {
+                    let sum = f64::from(*merged_avg) * f64::from(*merged_count)
+                        + f64::from(avg) * f64::from(count);
+                    *merged_avg = (sum / f64::from(total)) as Price;
+                }
+                *merged_count = total;
+            })
+            .or_insert((count, avg));
+    }
+    merged
+}
+
+/// The change of the average prices between two aggregation results, see [`diff_averages`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AverageDiff {
+    /// `curr - prev` for every network in both results.
+    pub changes: HashMap<NetworkName, Price>,
+    /// The networks only in `curr`, in [`NetworkName`] order.
+    pub added: Vec<NetworkName>,
+    /// The networks only in `prev`, in [`NetworkName`] order.
+    pub removed: Vec<NetworkName>,
+}
+
+/// Computes how the average price of every network changed from `prev` to `curr`, e.g. between
+/// two snapshots of a [`StreamingAggregator`](crate::StreamingAggregator).
+pub fn diff_averages(
+    prev: &HashMap<NetworkName, NetworkAggregate>,
+    curr: &HashMap<NetworkName, NetworkAggregate>,
+) -> AverageDiff {
+    let mut diff = AverageDiff::default();
+    for (name, (_, avg)) in curr {
+        match prev.get(name) {
+            Some((_, prev_avg)) => {
+                let change = f64::from(*avg) - f64::from(*prev_avg);
+                diff.changes.insert(*name, change as Price);
+            }
+            None => diff.added.push(*name),
+        }
+    }
+    diff.removed = prev
+        .keys()
+        .filter(|name| !curr.contains_key(*name))
+        .copied()
+        .collect();
+    diff.added.sort_unstable();
+    diff.removed.sort_unstable();
+    diff
+}
+
+/// How two aggregation results differ, see [`compare`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AggregateComparison {
+    /// `curr - prev` of the ticker counts for every network in both results.
+    pub count_delta: HashMap<NetworkName, i64>,
+    /// `curr - prev` of the average prices for every network in both results.
+    pub avg_delta: HashMap<NetworkName, Price>,
+    /// The networks only in `curr`, in [`NetworkName`] order.
+    pub added: Vec<NetworkName>,
+    /// The networks only in `prev`, in [`NetworkName`] order.
+    pub removed: Vec<NetworkName>,
+}
+
+/// Like [`diff_averages`], but also reports how the ticker count of every network changed.
+pub fn compare(
+    prev: &HashMap<NetworkName, NetworkAggregate>,
+    curr: &HashMap<NetworkName, NetworkAggregate>,
+) -> AggregateComparison {
+    let AverageDiff {
+        changes,
+        added,
+        removed,
+    } = diff_averages(prev, curr);
+    let count_delta = changes
+        .keys()
+        .map(|name| (*name, i64::from(curr[name].0) - i64::from(prev[name].0)))
+        .collect();
+
+    AggregateComparison {
+        count_delta,
+        avg_delta: changes,
+        added,
+        removed,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{aggregate_by_network_ref, AggregateError, OverflowPolicy};
+    use assert_float_eq::assert_float_absolute_eq;
+
+    #[test]
+    fn partial_aggregate_finalize() {
+        let mut partial = PartialAggregate::new();
+        assert_eq!(None, partial.finalize());
+
+        partial.add_price(1.0);
+        partial.add_price(2.0);
+        assert_eq!(2, partial.count());
+        assert_eq!(3.0, partial.sum());
+        assert_eq!(Some(1.5), partial.finalize());
+    }
+
+    #[test]
+    fn partial_aggregate_finalize_large_count() {
+        let partial = PartialAggregate {
+            count: u64::from(u32::MAX) * 4,
+            sum: f64::from(u32::MAX) * 10.0,
+            ..Default::default()
+        };
+
+        assert_eq!(Some(2.5), partial.finalize());
+    }
+
+    #[test]
+    #[should_panic(expected = "count doesn't overflow")]
+    fn finalize_aggregates_count_overflow() {
+        let mut partial = PartialAggregate {
+            count: u64::from(u32::MAX),
+            ..Default::default()
+        };
+        partial.merge(&PartialAggregate {
+            count: 1,
+            sum: 1.0,
+            ..Default::default()
+        });
+        assert_eq!(u64::from(u32::MAX) + 1, partial.count());
+
+        finalize_aggregates(HashMap::from([(NetworkName::N1, partial)]));
+    }
+
+    #[test]
+    fn partial_aggregate_add_prices_matches_add_price() {
+        let prices: Vec<_> = (0..1_003).map(|i| (i % 97) as Price / 7.0 + 1e3).collect();
+        let mut scalar = PartialAggregate::new();
+        for price in &prices {
+            scalar.add_price(*price);
+        }
+        let mut batched = PartialAggregate::new();
+        batched.add_prices(&prices[..10]);
+        batched.add_prices(&prices[10..]);
+
+        assert_eq!(scalar.count(), batched.count());
+        assert_float_absolute_eq!(scalar.sum(), batched.sum(), 1e-9);
+    }
+
+    #[test]
+    fn partial_aggregate_sum_is_order_independent() {
+        let sum = |prices: &[Price]| {
+            let mut partial = PartialAggregate::new();
+            for price in prices {
+                partial.add_price(*price);
+            }
+            partial.sum()
+        };
+
+        // a naive `f64` sum loses the 1.0 next to 1e16 in the first order
+        assert_eq!(1.0, sum(&[1e16, 1.0, -1e16]));
+        assert_eq!(1.0, sum(&[1.0, 1e16, -1e16]));
+        assert_eq!(1.0, sum(&[-1e16, 1e16, 1.0]));
+    }
+
+    #[test]
+    fn partial_aggregate_merge_matches_concatenated_prices() {
+        let (a, b): (&[Price], &[Price]) = (&[0.1, 0.2, 0.7], &[0.3, 0.5]);
+        let fold = |prices: &[Price]| {
+            let mut partial = PartialAggregate::new();
+            for price in prices {
+                partial.add_price(*price);
+            }
+            partial
+        };
+
+        let mut merged = fold(a);
+        merged.merge(&fold(b));
+
+        assert_eq!(5, merged.count());
+        assert_eq!(fold(&[a, b].concat()).finalize(), merged.finalize());
+    }
+
+    #[test]
+    fn partial_aggregate_checked_add_price_overflow() {
+        let mut partial = PartialAggregate {
+            count: u64::from(u32::MAX),
+            ..Default::default()
+        };
+
+        assert!(!partial.checked_add_price(1.0));
+        assert_eq!(u64::from(u32::MAX), partial.count());
+        assert_eq!(0.0, partial.sum());
+    }
+
+    #[test]
+    fn partial_aggregate_remove_price() {
+        let mut partial = PartialAggregate::new();
+        assert!(!partial.remove_price(1.0));
+
+        partial.add_price(1.0);
+        partial.add_price(3.0);
+        assert!(partial.remove_price(1.0));
+        assert_eq!(Some(3.0), partial.finalize());
+        assert!(partial.remove_price(3.0));
+        assert_eq!(PartialAggregate::new(), partial);
+    }
+
+    #[test]
+    #[should_panic(expected = "count doesn't overflow")]
+    fn partial_aggregate_add_price_overflow() {
+        let mut partial = PartialAggregate {
+            count: u64::MAX,
+            ..Default::default()
+        };
+        partial.add_price(1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "count doesn't overflow")]
+    fn partial_aggregate_merge_overflow() {
+        let mut partial = PartialAggregate {
+            count: u64::MAX,
+            ..Default::default()
+        };
+        partial.merge(&partial.clone());
+    }
+
+    #[test]
+    fn try_add_to_sums_reports_overflowing_network() {
+        let full = PartialAggregate {
+            count: u64::from(u32::MAX),
+            ..Default::default()
+        };
+        let mut sums = HashMap::from([(NetworkName::N2, full)]);
+
+        let add = |sums: &mut _, symbol| {
+            crate::try_add_to_sums(
+                sums,
+                Ticker::new(symbol, 1.0),
+                crate::PriceValidation::default(),
+                crate::lookup_symbol,
+            )
+        };
+        assert_eq!(Ok(()), add(&mut sums, "S1"));
+        assert_eq!(
+            Err(AggregateError::CountOverflow(NetworkName::N2)),
+            add(&mut sums, "S2")
+        );
+        assert_eq!(full, sums[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn try_add_to_sums_overflow_policies() {
+        let full = PartialAggregate {
+            count: u64::from(u32::MAX),
+            sum: f64::from(u32::MAX),
+            ..Default::default()
+        };
+        let add = |policy| {
+            let mut sums = HashMap::from([(NetworkName::N1, full)]);
+            let validation = crate::PriceValidation {
+                count_overflow: policy,
+                ..Default::default()
+            };
+            let result = crate::try_add_to_sums(
+                &mut sums,
+                Ticker::new("S1", 3.0),
+                validation,
+                crate::lookup_symbol,
+            );
+            (result, sums[&NetworkName::N1])
+        };
+
+        let (result, partial) = add(OverflowPolicy::Checked);
+        assert_eq!(Err(AggregateError::CountOverflow(NetworkName::N1)), result);
+        assert_eq!(full, partial);
+
+        let (result, partial) = add(OverflowPolicy::Saturating);
+        assert_eq!(Ok(()), result);
+        assert_eq!(full, partial);
+        assert_eq!(Some(1.0), partial.finalize());
+
+        let (result, partial) = add(OverflowPolicy::Wrapping);
+        assert_eq!(Ok(()), result);
+        assert_eq!(0, partial.count());
+        assert_eq!(f64::from(u32::MAX) + 3.0, partial.sum());
+    }
+
+    #[test]
+    fn merge_partial_aggregates_matches_concatenated_input() {
+        let shards = || {
+            (
+                vec![Ticker::new("S1", 0.1), Ticker::new("S2", 0.2)],
+                vec![
+                    Ticker::new("S1", 0.3),
+                    Ticker::new("S1", 0.5),
+                    Ticker::new("s3", 0.4),
+                ],
+            )
+        };
+        let (mut all, rest) = shards();
+        all.extend(rest);
+        let expected = aggregate_by_network_ref(&all);
+
+        let (a, b) = shards();
+
+        let merged = merge_partial_aggregates(
+            partial_aggregate_by_network(a),
+            partial_aggregate_by_network(b),
+        );
+
+        assert_eq!(expected, finalize_aggregates(merged));
+    }
+
+    #[test]
+    fn merge_aggregates_weights_by_count() {
+        let a = HashMap::from([(NetworkName::N1, (1, 0.1)), (NetworkName::N2, (1, 0.2))]);
+        let b = HashMap::from([(NetworkName::N1, (3, 0.5))]);
+        let merged = merge_aggregates(a, b);
+
+        assert_eq!(4, merged[&NetworkName::N1].0);
+        assert_float_absolute_eq!(0.4, merged[&NetworkName::N1].1, f32::EPSILON as Price);
+        assert_eq!((1, 0.2), merged[&NetworkName::N2]);
+    }
+
+    #[test]
+    fn diff_averages_reports_added_and_removed() {
+        let prev = HashMap::from([(NetworkName::N1, (2, 1.0)), (NetworkName::N2, (1, 2.0))]);
+        let curr = HashMap::from([(NetworkName::N1, (3, 1.5)), (NetworkName::N3, (1, 4.0))]);
+
+        assert_eq!(
+            AverageDiff {
+                changes: HashMap::from([(NetworkName::N1, 0.5)]),
+                added: vec![NetworkName::N3],
+                removed: vec![NetworkName::N2],
+            },
+            diff_averages(&prev, &curr)
+        );
+        assert_eq!(
+            HashMap::from([(NetworkName::N1, 0.0), (NetworkName::N3, 0.0)]),
+            diff_averages(&curr, &curr).changes
+        );
+    }
+
+    #[test]
+    fn compare_reports_deltas() {
+        let prev = HashMap::from([(NetworkName::N1, (3, 1.0)), (NetworkName::N2, (1, 2.0))]);
+        let curr = HashMap::from([
+            (NetworkName::N1, (2, 1.5)),
+            (NetworkName::N2, (4, 2.0)),
+            (NetworkName::N3, (1, 4.0)),
+        ]);
+
+        assert_eq!(
+            AggregateComparison {
+                count_delta: HashMap::from([(NetworkName::N1, -1), (NetworkName::N2, 3)]),
+                avg_delta: HashMap::from([(NetworkName::N1, 0.5), (NetworkName::N2, 0.0)]),
+                added: vec![NetworkName::N3],
+                removed: vec![],
+            },
+            compare(&prev, &curr)
+        );
+        assert_eq!(vec![NetworkName::N3], compare(&curr, &prev).removed);
+    }
+
+    #[test]
+    fn merge_aggregates_empty_groups() {
+        let a = HashMap::from([(NetworkName::N1, (0, 0.0))]);
+        let merged = merge_aggregates(a.clone(), a);
+
+        assert_eq!((0, 0.0), merged[&NetworkName::N1]);
+    }
+
+    #[test]
+    fn partial_aggregate_by_network_empty() {
+        let partial = partial_aggregate_by_network(vec![]);
+        assert!(partial.is_empty());
+
+        let merged = merge_partial_aggregates(partial, HashMap::new());
+        assert!(finalize_aggregates(merged).is_empty());
+    }
+}
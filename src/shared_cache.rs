@@ -0,0 +1,101 @@
+use std::sync::{PoisonError, RwLock};
+
+use crate::{track_entry, HashMap, NetworkName};
+
+/// A symbol cache that can be shared between threads, see [`shared_name_from_symbol`].
+///
+/// Like the caches of [`name_from_symbol`](crate::name_from_symbol), it's keyed by the `'static`
+/// symbols from `TRACK`.
+#[derive(Debug, Default)]
+pub struct SharedCache {
+    map: RwLock<HashMap<&'static str, NetworkName>>,
+}
+
+impl SharedCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of cached symbols.
+    pub fn len(&self) -> usize {
+        self.map
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns `true` if nothing is cached yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Resolves `symbol` to its network, memoizing the lookup in the shared `cache`.
+///
+/// # Panics
+///
+/// Panics if `symbol` isn't known. Use [`try_shared_name_from_symbol`] for untrusted input.
+pub fn shared_name_from_symbol(symbol: &str, cache: &SharedCache) -> NetworkName {
+    try_shared_name_from_symbol(symbol, cache).expect("symbol is valid")
+}
+
+/// Resolves `symbol` to its network, memoizing the lookup in the shared `cache`.
+///
+/// Hits only take the read lock, the write lock is taken on a miss. Leading and trailing ASCII
+/// whitespace in `symbol` is ignored. Returns `None` if `symbol` isn't known.
+pub fn try_shared_name_from_symbol(symbol: &str, cache: &SharedCache) -> Option<NetworkName> {
+    let symbol = symbol.trim_ascii();
+
+    // the map only ever gains entries copied from TRACK, so a panic while holding the lock can't
+    // leave it inconsistent
+    let hit = cache
+        .map
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(symbol)
+        .copied();
+    if hit.is_some() {
+        return hit;
+    }
+
+    let (key, value) = track_entry(symbol)?;
+    cache
+        .map
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key, value);
+    Some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shared_name_from_symbol_correct() {
+        let cache = SharedCache::new();
+
+        assert_eq!(NetworkName::N1, shared_name_from_symbol("S1", &cache));
+        assert_eq!(NetworkName::N1, shared_name_from_symbol(" S1", &cache));
+        assert_eq!(None, try_shared_name_from_symbol("BTC", &cache));
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn shared_name_from_symbol_across_threads() {
+        let cache = SharedCache::new();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        assert_eq!(NetworkName::N2, shared_name_from_symbol("S2", &cache));
+                        assert_eq!(NetworkName::N3, shared_name_from_symbol("s3", &cache));
+                    }
+                });
+            }
+        });
+
+        assert_eq!(2, cache.len());
+    }
+}
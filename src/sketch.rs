@@ -0,0 +1,226 @@
+use alloc::vec::Vec;
+
+use tdigest::TDigest;
+
+use crate::{expect_network, HashMap, InvalidPercentileError, NetworkName, Price, Ticker};
+
+/// The maximum number of centroids each network's t-digest keeps.
+///
+/// More centroids give more accurate percentiles, especially close to the median, at the cost of
+/// memory and merge time. With 100 centroids the estimates are typically within a fraction of a
+/// percent of the true rank, and most accurate at the tails.
+pub const TDIGEST_COMPRESSION: usize = 100;
+
+// prices are buffered per network and merged into its digest in batches of this size
+const BUFFER_LEN: usize = 1024;
+
+/// An approximate distribution of prices: a t-digest of at most [`TDIGEST_COMPRESSION`]
+/// centroids and a small buffer, so memory doesn't grow with the number of prices.
+///
+/// Sketches built from separate inputs, e.g. on separate machines, can be
+/// [`merge`](Self::merge)d, and the merged sketch approximates the distribution of the union of the
+/// inputs. With the `serde` feature sketches can be serialized to ship them to wherever they're
+/// merged.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceSketch {
+    digest: TDigest,
+    buffer: Vec<f64>,
+}
+
+impl PriceSketch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `price` to the sketch. Non-finite prices are ignored.
+    pub fn add(&mut self, price: Price) {
+        if !price.is_finite() {
+            return;
+        }
+        self.buffer.push(f64::from(price));
+        if self.buffer.len() == BUFFER_LEN {
+            self.flush();
+        }
+    }
+
+    /// Folds `other` into `self`, as if all of its prices had been added to `self`.
+    pub fn merge(&mut self, other: &Self) {
+        self.flush();
+        let other = other.digest.merge_unsorted(other.buffer.clone());
+        if other.is_empty() {
+            return;
+        }
+        self.digest = TDigest::merge_digests(Vec::from([core::mem::take(&mut self.digest), other]));
+    }
+
+    /// Forgets every price added so far.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the number of prices in the sketch.
+    pub fn count(&self) -> u64 {
+        self.digest.count() as u64 + self.buffer.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Estimates the `p`th percentile of the prices, with `p` in `[0.0, 100.0]`.
+    ///
+    /// Returns `Ok(None)` if no prices were added.
+    pub fn percentile(&self, p: f64) -> Result<Option<Price>, InvalidPercentileError> {
+        if !(0.0..=100.0).contains(&p) {
+            return Err(InvalidPercentileError(p));
+        }
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let digest = self.digest.merge_unsorted(self.buffer.clone());
+        Ok(Some(digest.estimate_quantile(p / 100.0) as Price))
+    }
+
+    fn flush(&mut self) {
+        self.digest = self
+            .digest
+            .merge_unsorted(core::mem::take(&mut self.buffer));
+    }
+}
+
+impl Default for PriceSketch {
+    fn default() -> Self {
+        Self {
+            digest: TDigest::new_with_size(TDIGEST_COMPRESSION),
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Builds a [`PriceSketch`] of the prices per network, to be merged with sketches of other inputs
+/// or queried later.
+///
+/// Networks whose prices are all non-finite are left out.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn sketch_by_network(
+    tickers: impl IntoIterator<Item = Ticker>,
+) -> HashMap<NetworkName, PriceSketch> {
+    let mut sketches = HashMap::<NetworkName, PriceSketch>::new();
+    for val in tickers {
+        let name = expect_network(&val.symbol);
+        if val.price.is_finite() {
+            sketches.entry(name).or_default().add(val.price);
+        }
+    }
+    sketches
+}
+
+/// Estimates the `p`th percentile of the price per network, with `p` in `[0.0, 100.0]`.
+///
+/// Unlike [`percentile_by_network`](crate::percentile_by_network) this doesn't keep every price:
+/// each network only holds a [`PriceSketch`], so memory doesn't grow with the input. The price is
+/// an approximation. Non-finite prices are left out.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub fn approx_percentile_by_network(
+    tickers: impl IntoIterator<Item = Ticker>,
+    p: f64,
+) -> Result<HashMap<NetworkName, Price>, InvalidPercentileError> {
+    if !(0.0..=100.0).contains(&p) {
+        return Err(InvalidPercentileError(p));
+    }
+
+    let mut result = HashMap::new();
+    for (name, sketch) in sketch_by_network(tickers) {
+        if let Some(price) = sketch.percentile(p)? {
+            result.insert(name, price);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::percentile_by_network;
+
+    #[test]
+    fn approx_percentile_by_network_close_to_exact() {
+        let tickers: Vec<_> = (0..10_000)
+            .map(|i| Ticker::new("S1", (i * 7919 % 10_000) as Price))
+            .collect();
+        let exact = percentile_by_network(&tickers, 95.0).unwrap();
+        let approx = approx_percentile_by_network(tickers, 95.0).unwrap();
+
+        let error = (exact[&NetworkName::N1] - approx[&NetworkName::N1]).abs();
+        assert!(error < 50.0, "error {error} is too large");
+    }
+
+    #[test]
+    fn price_sketch_merge_approximates_union() {
+        let tickers: Vec<_> = (0..10_000)
+            .map(|i| Ticker::new("S1", (i * 7919 % 10_000) as Price))
+            .collect();
+        let exact = percentile_by_network(&tickers, 50.0).unwrap()[&NetworkName::N1];
+
+        let (a, b) = tickers.split_at(3_000);
+        let mut merged = sketch_by_network(a.to_vec())
+            .remove(&NetworkName::N1)
+            .unwrap();
+        merged.merge(&sketch_by_network(b.to_vec())[&NetworkName::N1]);
+
+        assert_eq!(10_000, merged.count());
+        let error = (exact - merged.percentile(50.0).unwrap().unwrap()).abs();
+        assert!(error < 50.0, "error {error} is too large");
+    }
+
+    #[test]
+    fn price_sketch_reset() {
+        let mut sketch = PriceSketch::new();
+        sketch.add(1.0);
+        sketch.merge(&PriceSketch::new());
+        assert_eq!(Ok(Some(1.0)), sketch.percentile(50.0));
+
+        sketch.reset();
+        assert!(sketch.is_empty());
+        assert_eq!(Ok(None), sketch.percentile(50.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn price_sketch_serde_roundtrip() {
+        let mut sketch = PriceSketch::new();
+        for price in 0..2_000 {
+            sketch.add(price as Price);
+        }
+        let json = serde_json::to_string(&sketch).unwrap();
+        let parsed: PriceSketch = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(sketch.count(), parsed.count());
+        assert_eq!(sketch.percentile(90.0), parsed.percentile(90.0));
+    }
+
+    #[test]
+    fn approx_percentile_by_network_out_of_range() {
+        assert_eq!(
+            Err(InvalidPercentileError(101.0)),
+            approx_percentile_by_network(vec![Ticker::new("S1", 1.0)], 101.0)
+        );
+    }
+
+    #[test]
+    fn approx_percentile_by_network_empty() {
+        assert_eq!(
+            Ok(HashMap::new()),
+            approx_percentile_by_network(vec![], 50.0)
+        );
+        assert!(sketch_by_network(vec![]).is_empty());
+    }
+}
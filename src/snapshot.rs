@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{HashMap, NetworkName, PartialAggregate};
+
+/// The format version written by [`Snapshot::to_bytes`].
+///
+/// It's bumped whenever the format changes in a way older versions of this crate can't read.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// The durable state of an aggregation: the [`PartialAggregate`] of every network, tagged with the
+/// version of the format.
+///
+/// Snapshots are serialized as JSON, e.g.
+/// `{"version":1,"networks":{"S1":{"count":2,"sum":0.4,"compensation":0.0}}}`, with the networks
+/// in [`NetworkName`] order, so equal snapshots give equal bytes. The sums are kept as they are,
+/// so aggregating on from a reloaded snapshot gives the same averages as if there had been no
+/// restart.
+///
+/// ```
+/// use bhtest::{Snapshot, StreamingAggregator, Ticker};
+///
+/// let mut aggregator = StreamingAggregator::new();
+/// aggregator.push(Ticker::new("S1", 1.0)).unwrap();
+///
+/// let bytes = aggregator.to_snapshot().to_bytes();
+/// let restored = StreamingAggregator::from_snapshot(Snapshot::from_bytes(&bytes).unwrap());
+///
+/// assert_eq!(aggregator.snapshot(), restored.snapshot());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    version: u32,
+    networks: BTreeMap<NetworkName, PartialAggregate>,
+}
+
+// the part of every version that says which version it is
+#[derive(serde::Deserialize)]
+struct Header {
+    version: u32,
+}
+
+impl Snapshot {
+    pub fn new(partials: HashMap<NetworkName, PartialAggregate>) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            networks: partials.into_iter().collect(),
+        }
+    }
+
+    /// Returns the partial aggregate of every network, to be merged or finalized.
+    pub fn into_partials(self) -> HashMap<NetworkName, PartialAggregate> {
+        self.networks.into_iter().collect()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("serializing a snapshot can't fail")
+    }
+
+    /// Reads a snapshot written by [`to_bytes`](Self::to_bytes).
+    ///
+    /// The version is read first, so a snapshot of a version this crate doesn't know fails with
+    /// [`SnapshotError::UnsupportedVersion`] rather than with whatever part of it doesn't parse.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let header: Header = serde_json::from_slice(bytes).map_err(SnapshotError::Json)?;
+        if header.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(header.version));
+        }
+        serde_json::from_slice(bytes).map_err(SnapshotError::Json)
+    }
+}
+
+/// The error returned by [`Snapshot::from_bytes`].
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The input isn't a snapshot of the version it claims to be.
+    Json(serde_json::Error),
+    /// The snapshot was written in a version other than [`SNAPSHOT_VERSION`].
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "invalid snapshot: {err}"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported snapshot version {version}, expected {SNAPSHOT_VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Json(err) => Some(err),
+            Self::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{partial_aggregate_by_network, Ticker};
+
+    #[test]
+    fn snapshot_bytes_round_trip() {
+        let partials = partial_aggregate_by_network(vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("S1", 0.2),
+            Ticker::new("s3", 1e8),
+            Ticker::new("s3", 1e-8),
+        ]);
+        let snapshot = Snapshot::new(partials.clone());
+        let restored = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+
+        assert_eq!(snapshot, restored);
+        assert_eq!(partials, restored.into_partials());
+    }
+
+    #[test]
+    fn snapshot_format() {
+        let snapshot = Snapshot::new(HashMap::from([
+            (NetworkName::N2, PartialAggregate::default()),
+            (NetworkName::N1, PartialAggregate::default()),
+        ]));
+
+        assert_eq!(
+            r#"{"version":1,"networks":{"S1":{"count":0,"sum":0.0,"compensation":0.0},"S2":{"count":0,"sum":0.0,"compensation":0.0}}}"#,
+            String::from_utf8(snapshot.to_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn snapshot_unsupported_version() {
+        let bytes = br#"{"version":2,"networks":[1, 2, 3],"rates":{}}"#;
+        let err = Snapshot::from_bytes(bytes).unwrap_err();
+
+        assert!(matches!(err, SnapshotError::UnsupportedVersion(2)));
+        assert_eq!(
+            "unsupported snapshot version 2, expected 1",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn snapshot_malformed() {
+        assert!(matches!(
+            Snapshot::from_bytes(b"{}"),
+            Err(SnapshotError::Json(_))
+        ));
+        assert!(matches!(
+            Snapshot::from_bytes(br#"{"version":1,"networks":{"N1":{}}}"#),
+            Err(SnapshotError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+use core::pin::pin;
+
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+    add_to_sums, averages, expect_network, HashMap, NetworkAggregate, NetworkName, Ticker,
+};
+
+/// Like [`aggregate_by_network`](crate::aggregate_by_network), but folds the tickers as they
+/// arrive from an async `stream`, without collecting them first.
+///
+/// The result covers every ticker the stream yielded before it ended. Dropping the future drops
+/// the partial sums with it.
+///
+/// # Panics
+///
+/// Panics if a ticker's symbol isn't known.
+pub async fn aggregate_stream<S: Stream<Item = Ticker>>(
+    stream: S,
+) -> HashMap<NetworkName, NetworkAggregate> {
+    let mut stream = pin!(stream);
+    let mut sums = HashMap::new();
+    while let Some(val) = stream.next().await {
+        add_to_sums(&mut sums, expect_network(&val.symbol), val.price);
+    }
+
+    averages(sums)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aggregate_by_network_ref;
+    use futures_executor::block_on;
+    use futures_util::stream;
+
+    #[test]
+    fn aggregate_stream_matches_slice() {
+        let tickers = vec![
+            Ticker::new("S1", 0.1),
+            Ticker::new("S2", 0.2),
+            Ticker::new("S1", 0.3),
+        ];
+        let expected = aggregate_by_network_ref(&tickers);
+
+        assert_eq!(expected, block_on(aggregate_stream(stream::iter(tickers))));
+    }
+
+    #[test]
+    fn aggregate_stream_empty() {
+        assert!(block_on(aggregate_stream(stream::empty())).is_empty());
+    }
+}
@@ -0,0 +1,442 @@
+#[cfg(feature = "json")]
+use crate::Snapshot;
+use crate::{
+    averages, lookup_symbol, try_add_to_sums, AggregateError, HashMap, InvalidPercentileError,
+    NetworkAggregate, NetworkName, PartialAggregate, Price, PriceValidation, Ticker,
+};
+
+/// Aggregates tickers one at a time, e.g. as they arrive from a live feed.
+///
+/// Only the running count and price sum per network are kept, and averages are computed when a
+/// [`snapshot`](Self::snapshot) is taken.
+///
+/// A strict aggregator, the default, rejects tickers with unknown symbols, and a lenient one skips
+/// them and counts how many it skipped.
+///
+/// An aggregator set up [`with_percentile`](Self::with_percentile) also estimates one percentile
+/// of the prices per network, see [`percentile_estimate`](Self::percentile_estimate).
+#[derive(Clone, Debug, Default)]
+pub struct StreamingAggregator {
+    sums: HashMap<NetworkName, PartialAggregate>,
+    lenient: bool,
+    skipped: u64,
+    // the percentile as a fraction in `[0, 1]`, if one is estimated
+    percentile: Option<f64>,
+    estimates: HashMap<NetworkName, P2Estimate>,
+}
+
+impl StreamingAggregator {
+    /// Same as [`new_strict`](Self::new_strict).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an aggregator whose [`push`](Self::push) fails on unknown symbols.
+    pub fn new_strict() -> Self {
+        Self::default()
+    }
+
+    /// Creates an aggregator whose [`push`](Self::push) skips unknown symbols.
+    pub fn new_lenient() -> Self {
+        Self {
+            lenient: true,
+            ..Self::default()
+        }
+    }
+
+    /// Makes the aggregator also estimate the `p`th percentile of the prices per network, with
+    /// `p` in `[0.0, 100.0]`, e.g. 95 for the p95. Works on strict and lenient aggregators alike,
+    /// e.g. `StreamingAggregator::new_lenient().with_percentile(95.0)`.
+    ///
+    /// The estimate only keeps what it needs for this one percentile, so it starts over from the
+    /// tickers pushed afterwards, see [`percentile_estimate`](Self::percentile_estimate).
+    pub fn with_percentile(mut self, p: f64) -> Result<Self, InvalidPercentileError> {
+        if !(0.0..=100.0).contains(&p) {
+            return Err(InvalidPercentileError(p));
+        }
+        self.percentile = Some(p / 100.0);
+        self.estimates.clear();
+        Ok(self)
+    }
+
+    /// Folds `ticker` into the running aggregate, and returns how many tickers with unknown symbols
+    /// were skipped so far, which is always 0 unless the aggregator is lenient.
+    ///
+    /// Tickers are validated like in [`try_aggregate_by_network`](crate::try_aggregate_by_network),
+    /// except that a lenient aggregator skips unknown symbols instead of returning
+    /// [`AggregateError::UnknownSymbol`]. A rejected ticker leaves the aggregate unchanged.
+    pub fn push(&mut self, ticker: Ticker) -> Result<u64, AggregateError> {
+        if self.lenient && lookup_symbol(&ticker.symbol).is_none() {
+            self.skipped += 1;
+            return Ok(self.skipped);
+        }
+        let price = ticker.price;
+        let mut name = None;
+        try_add_to_sums(
+            &mut self.sums,
+            ticker,
+            PriceValidation::default(),
+            |symbol| {
+                name = lookup_symbol(symbol);
+                name
+            },
+        )?;
+        if let (Some(p), Some(name)) = (self.percentile, name) {
+            self.estimates.entry(name).or_default().add(p, price);
+        }
+        Ok(self.skipped)
+    }
+
+    /// Takes a previously pushed `ticker` back out of the running aggregate, e.g. when it was
+    /// cancelled.
+    ///
+    /// Returns [`AggregateError::CountUnderflow`] if the ticker's network has nothing left to
+    /// remove, and leaves the aggregate unchanged. Removing a ticker that was never pushed but
+    /// whose network has other tickers can't be detected and skews the average.
+    pub fn remove(&mut self, ticker: &Ticker) -> Result<(), AggregateError> {
+        let name = lookup_symbol(&ticker.symbol)
+            .ok_or_else(|| AggregateError::UnknownSymbol(ticker.symbol.clone().into_owned()))?;
+        let Some(sums) = self.sums.get_mut(&name) else {
+            return Err(AggregateError::CountUnderflow(name));
+        };
+        if !sums.remove_price(ticker.price) {
+            return Err(AggregateError::CountUnderflow(name));
+        }
+        if sums.count() == 0 {
+            self.sums.remove(&name);
+        }
+        Ok(())
+    }
+
+    /// Returns the count and average price per network of everything pushed so far.
+    ///
+    /// Only the running sums are divided, so this takes time proportional to the number of
+    /// networks, not to the number of tickers pushed.
+    pub fn snapshot(&self) -> HashMap<NetworkName, NetworkAggregate> {
+        averages(self.sums.iter().map(|(name, sums)| (*name, *sums)))
+    }
+
+    /// Returns the estimate of the percentile chosen in [`with_percentile`](Self::with_percentile)
+    /// per network, or an empty map if the aggregator doesn't estimate one.
+    ///
+    /// The estimate uses the P² algorithm, which keeps five markers per network, so memory and the
+    /// cost of a push stay constant however many tickers are pushed. As long as a network has
+    /// fewer than five tickers, the estimate is exact and interpolated like in
+    /// [`percentile_by_network`](crate::percentile_by_network). After that it's an approximation
+    /// without an error bound: for smooth distributions with many tickers it's typically close to
+    /// the true percentile, but it can be noticeably off for few tickers, for heavy tails, for
+    /// percentiles close to 0 or 100, or when the distribution drifts over time. It also depends on
+    /// the order of the pushes. The `PriceSketch` of the `tdigest` feature gives more accurate
+    /// estimates of any percentile.
+    ///
+    /// Tickers taken back out with [`remove`](Self::remove) stay in the estimate.
+    pub fn percentile_estimate(&self) -> HashMap<NetworkName, Price> {
+        self.estimates
+            .iter()
+            .map(|(name, estimate)| (*name, estimate.value(self.percentile.unwrap_or_default())))
+            .collect()
+    }
+
+    /// Returns the number of tickers pushed and not removed so far, across all networks.
+    pub fn len(&self) -> usize {
+        self.sums.values().map(|sums| sums.count() as usize).sum()
+    }
+
+    /// Returns `true` if no tickers were pushed, or all of them were removed again.
+    pub fn is_empty(&self) -> bool {
+        self.sums.is_empty()
+    }
+
+    /// Forgets everything pushed so far, including the count of skipped tickers and the percentile
+    /// estimates. The percentile to estimate is kept.
+    pub fn clear(&mut self) {
+        self.sums.clear();
+        self.skipped = 0;
+        self.estimates.clear();
+    }
+}
+
+#[cfg(feature = "json")]
+impl StreamingAggregator {
+    /// Returns the running sums as a [`Snapshot`], e.g. to persist them across restarts.
+    ///
+    /// The count of skipped tickers and the percentile estimates aren't part of it.
+    pub fn to_snapshot(&self) -> Snapshot {
+        Snapshot::new(self.sums.clone())
+    }
+
+    /// Creates a strict aggregator that carries on from `snapshot`.
+    pub fn from_snapshot(snapshot: Snapshot) -> Self {
+        Self {
+            sums: snapshot.into_partials(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Pushes every ticker. Only the counts and sums are updated, averages are still computed by
+/// [`snapshot`](StreamingAggregator::snapshot).
+///
+/// # Panics
+///
+/// Panics on the first ticker [`push`](StreamingAggregator::push) rejects. Push them one by one to
+/// handle invalid tickers instead.
+impl Extend<Ticker> for StreamingAggregator {
+    fn extend<I: IntoIterator<Item = Ticker>>(&mut self, tickers: I) {
+        for ticker in tickers {
+            self.push(ticker).expect("ticker is valid");
+        }
+    }
+}
+
+// the state of the P² algorithm (Jain and Chlamtac, 1985) for a single percentile `p`
+#[derive(Clone, Debug, Default)]
+struct P2Estimate {
+    count: usize,
+    // the marker heights, and the first prices until there are five of them
+    heights: [f64; 5],
+    // the actual marker positions, 0-based
+    positions: [f64; 5],
+}
+
+impl P2Estimate {
+    fn add(&mut self, p: f64, price: Price) {
+        let price = f64::from(price);
+        if self.count < 5 {
+            self.heights[self.count] = price;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_unstable_by(f64::total_cmp);
+                self.positions = [0.0, 1.0, 2.0, 3.0, 4.0];
+            }
+            return;
+        }
+
+        // the cell of the markers `price` falls into, widening the outer markers if needed
+        let cell = if price < self.heights[0] {
+            self.heights[0] = price;
+            0
+        } else if price >= self.heights[4] {
+            self.heights[4] = price;
+            3
+        } else {
+            (0..4)
+                .find(|&i| price < self.heights[i + 1])
+                .expect("price is below the last marker")
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        self.count += 1;
+
+        let last = (self.count - 1) as f64;
+        let desired = [0.0, last * p / 2.0, last * p, last * (1.0 + p) / 2.0, last];
+        for i in 1..4 {
+            let d = desired[i] - self.positions[i];
+            let (q, n) = (&mut self.heights, &mut self.positions);
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = q[i]
+                    + d / (n[i + 1] - n[i - 1])
+                        * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                            + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]));
+                q[i] = if q[i - 1] < parabolic && parabolic < q[i + 1] {
+                    parabolic
+                } else {
+                    // `d` is ±1, so this is the neighbor in its direction
+                    let j = if d > 0.0 { i + 1 } else { i - 1 };
+                    q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+                };
+                n[i] += d;
+            }
+        }
+    }
+
+    fn value(&self, p: f64) -> Price {
+        if self.count >= 5 {
+            return self.heights[2] as Price;
+        }
+        let mut prices = self.heights;
+        let prices = &mut prices[..self.count];
+        prices.sort_unstable_by(f64::total_cmp);
+        let rank = p * (prices.len() - 1) as f64;
+        // `rank` is within `[0, len - 1]`, so truncating gives the lower index
+        let low = rank as usize;
+        let high = (low + 1).min(prices.len() - 1);
+        (prices[low] + (prices[high] - prices[low]) * (rank - low as f64)) as Price
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Price;
+
+    #[test]
+    fn streaming_aggregator_snapshot() {
+        let mut aggregator = StreamingAggregator::new();
+        aggregator.push(Ticker::new("S1", 1.0)).unwrap();
+        aggregator.push(Ticker::new("S2", 2.0)).unwrap();
+
+        let snapshot = aggregator.snapshot();
+        assert_eq!((1, 1.0), snapshot[&NetworkName::N1]);
+        assert_eq!((1, 2.0), snapshot[&NetworkName::N2]);
+
+        aggregator.push(Ticker::new("S1", 2.0)).unwrap();
+        assert_eq!((2, 1.5), aggregator.snapshot()[&NetworkName::N1]);
+    }
+
+    #[test]
+    fn streaming_aggregator_extend() {
+        let mut aggregator = StreamingAggregator::new();
+        aggregator.extend(vec![Ticker::new("S1", 1.0), Ticker::new("S2", 2.0)]);
+        aggregator.extend(vec![Ticker::new("S1", 3.0)]);
+
+        let snapshot = aggregator.snapshot();
+        assert_eq!((2, 2.0), snapshot[&NetworkName::N1]);
+        assert_eq!((1, 2.0), snapshot[&NetworkName::N2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ticker is valid")]
+    fn streaming_aggregator_extend_unknown_symbol() {
+        StreamingAggregator::new().extend(vec![Ticker::new("BTC", 1.0)]);
+    }
+
+    #[test]
+    fn streaming_aggregator_rejects_invalid_tickers() {
+        let mut aggregator = StreamingAggregator::new();
+
+        assert_eq!(
+            Err(AggregateError::UnknownSymbol(String::from("foo"))),
+            aggregator.push(Ticker::new("foo", 1.0))
+        );
+        assert!(aggregator.push(Ticker::new("S1", Price::NAN)).is_err());
+        assert!(aggregator.snapshot().is_empty());
+    }
+
+    #[test]
+    fn streaming_aggregator_lenient_skips_unknown_symbols() {
+        let mut aggregator = StreamingAggregator::new_lenient();
+
+        assert_eq!(Ok(1), aggregator.push(Ticker::new("foo", 1.0)));
+        assert_eq!(Ok(1), aggregator.push(Ticker::new("S1", 2.0)));
+        assert_eq!(Ok(2), aggregator.push(Ticker::new("bar", 1.0)));
+        assert!(aggregator.push(Ticker::new("S1", Price::NAN)).is_err());
+        assert_eq!((1, 2.0), aggregator.snapshot()[&NetworkName::N1]);
+
+        aggregator.clear();
+        assert_eq!(Ok(1), aggregator.push(Ticker::new("foo", 1.0)));
+    }
+
+    #[test]
+    fn streaming_aggregator_strict_rejects_unknown_symbols() {
+        let mut aggregator = StreamingAggregator::new_strict();
+
+        assert!(aggregator.push(Ticker::new("foo", 1.0)).is_err());
+        assert_eq!(Ok(0), aggregator.push(Ticker::new("S1", 2.0)));
+    }
+
+    #[test]
+    fn streaming_aggregator_remove() {
+        let mut aggregator = StreamingAggregator::new();
+        aggregator.push(Ticker::new("S1", 1.0)).unwrap();
+        aggregator.push(Ticker::new("S1", 3.0)).unwrap();
+        aggregator.push(Ticker::new("S2", 2.0)).unwrap();
+
+        aggregator.remove(&Ticker::new("S1", 1.0)).unwrap();
+        assert_eq!((1, 3.0), aggregator.snapshot()[&NetworkName::N1]);
+
+        aggregator.remove(&Ticker::new("S2", 2.0)).unwrap();
+        assert!(!aggregator.snapshot().contains_key(&NetworkName::N2));
+    }
+
+    #[test]
+    fn streaming_aggregator_remove_underflow() {
+        let mut aggregator = StreamingAggregator::new();
+        aggregator.push(Ticker::new("S1", 1.0)).unwrap();
+
+        assert_eq!(
+            Err(AggregateError::CountUnderflow(NetworkName::N2)),
+            aggregator.remove(&Ticker::new("S2", 1.0))
+        );
+        aggregator.remove(&Ticker::new("S1", 1.0)).unwrap();
+        assert_eq!(
+            Err(AggregateError::CountUnderflow(NetworkName::N1)),
+            aggregator.remove(&Ticker::new("S1", 1.0))
+        );
+    }
+
+    #[test]
+    fn streaming_aggregator_len() {
+        let mut aggregator = StreamingAggregator::new();
+        assert!(aggregator.is_empty());
+
+        aggregator.extend(vec![
+            Ticker::new("S1", 1.0),
+            Ticker::new("S1", 2.0),
+            Ticker::new("S2", 2.0),
+        ]);
+        assert_eq!(3, aggregator.len());
+        assert!(!aggregator.is_empty());
+
+        aggregator.remove(&Ticker::new("S2", 2.0)).unwrap();
+        assert_eq!(2, aggregator.len());
+    }
+
+    #[test]
+    fn streaming_aggregator_percentile_estimate_exact_for_few_tickers() {
+        let mut aggregator = StreamingAggregator::new().with_percentile(50.0).unwrap();
+        assert!(aggregator.percentile_estimate().is_empty());
+
+        aggregator.extend(vec![
+            Ticker::new("S1", 3.0),
+            Ticker::new("S1", 1.0),
+            Ticker::new("S2", 2.0),
+        ]);
+        let estimate = aggregator.percentile_estimate();
+        assert_eq!(2.0, estimate[&NetworkName::N1]);
+        assert_eq!(2.0, estimate[&NetworkName::N2]);
+
+        assert!(StreamingAggregator::new().percentile_estimate().is_empty());
+        assert_eq!(
+            Err(InvalidPercentileError(101.0)),
+            StreamingAggregator::new()
+                .with_percentile(101.0)
+                .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn streaming_aggregator_lenient_with_percentile() {
+        let mut aggregator = StreamingAggregator::new_lenient()
+            .with_percentile(50.0)
+            .unwrap();
+
+        assert_eq!(Ok(1), aggregator.push(Ticker::new("BTC", 1.0)));
+        assert_eq!(Ok(1), aggregator.push(Ticker::new("S1", 4.0)));
+        assert_eq!(4.0, aggregator.percentile_estimate()[&NetworkName::N1]);
+    }
+
+    #[test]
+    fn streaming_aggregator_percentile_estimate_converges() {
+        let mut aggregator = StreamingAggregator::new().with_percentile(95.0).unwrap();
+        // 0..1000 in a scrambled order
+        aggregator.extend((0..1000).map(|i| Ticker::new("S1", ((i * 7919) % 1000) as Price)));
+
+        let estimate = aggregator.percentile_estimate()[&NetworkName::N1];
+        assert!((940.0..=960.0).contains(&estimate), "{estimate}");
+
+        aggregator.clear();
+        assert!(aggregator.percentile_estimate().is_empty());
+    }
+
+    #[test]
+    fn streaming_aggregator_clear() {
+        let mut aggregator = StreamingAggregator::new();
+        aggregator.push(Ticker::new("S1", 1.0)).unwrap();
+        aggregator.clear();
+
+        assert!(aggregator.snapshot().is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::collections::VecDeque;
+
+use crate::{
+    lookup_symbol, AggregateError, HashMap, NetworkName, PartialAggregate, Price, PriceValidation,
+    Ticker, Timestamp,
+};
+
+/// Averages the prices per network over a sliding window of time.
+///
+/// Tickers are expected to be pushed in timestamp order. Every network keeps its prices in a queue
+/// next to their running sum, so evicting the ones that left the window only touches those, and an
+/// average costs the evicted tickers and one division per network.
+#[derive(Clone, Debug)]
+pub struct WindowedAggregator {
+    window: Timestamp,
+    networks: HashMap<NetworkName, NetworkWindow>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct NetworkWindow {
+    prices: VecDeque<(Timestamp, Price)>,
+    // the sum of `prices`
+    sums: PartialAggregate,
+}
+
+impl WindowedAggregator {
+    /// Creates an aggregator over the last `window` of time.
+    pub fn new(window: Timestamp) -> Self {
+        Self {
+            window,
+            networks: HashMap::new(),
+        }
+    }
+
+    /// Adds `ticker` to the window.
+    ///
+    /// Tickers are validated like in [`try_aggregate_by_network`](crate::try_aggregate_by_network).
+    pub fn push(&mut self, ticker: Ticker) -> Result<(), AggregateError> {
+        let Some(name) = lookup_symbol(&ticker.symbol) else {
+            return Err(AggregateError::UnknownSymbol(ticker.symbol.into_owned()));
+        };
+        if !PriceValidation::default().accepts(ticker.price) {
+            return Err(AggregateError::InvalidPrice {
+                symbol: ticker.symbol.into_owned(),
+                price: ticker.price,
+            });
+        }
+        let network = self.networks.entry(name).or_default();
+        network.prices.push_back((ticker.timestamp, ticker.price));
+        network.sums.add_price(ticker.price);
+        Ok(())
+    }
+
+    /// Evicts the tickers older than `now - window` and returns the average price per network of
+    /// the rest.
+    ///
+    /// Networks without tickers in the window are left out.
+    pub fn average(&mut self, now: Timestamp) -> HashMap<NetworkName, Price> {
+        let start = now.saturating_sub(self.window);
+        let mut result = HashMap::new();
+        for (name, network) in &mut self.networks {
+            while let Some((timestamp, price)) = network.prices.front().copied() {
+                if timestamp >= start {
+                    break;
+                }
+                network.prices.pop_front();
+                network.sums.remove_price(price);
+            }
+            if let Some(avg) = network.sums.finalize() {
+                result.insert(*name, avg);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn at(secs: u64, symbol: &str, price: Price) -> Ticker {
+        Ticker::new(symbol, price).with_timestamp(Timestamp::from_secs(secs))
+    }
+
+    #[test]
+    fn windowed_aggregator_evicts_old_tickers() {
+        let mut aggregator = WindowedAggregator::new(Timestamp::from_secs(10));
+        aggregator.push(at(0, "S1", 1.0)).unwrap();
+        aggregator.push(at(5, "S1", 2.0)).unwrap();
+        aggregator.push(at(12, "S1", 4.0)).unwrap();
+        aggregator.push(at(1, "S2", 8.0)).unwrap();
+
+        let average = aggregator.average(Timestamp::from_secs(12));
+        assert_eq!(HashMap::from([(NetworkName::N1, 3.0)]), average);
+
+        let average = aggregator.average(Timestamp::from_secs(30));
+        assert!(average.is_empty());
+    }
+
+    #[test]
+    fn windowed_aggregator_running_sums_match_window() {
+        let mut aggregator = WindowedAggregator::new(Timestamp::from_secs(2));
+        for secs in 0..100 {
+            aggregator
+                .push(at(secs, "S1", (secs % 7) as Price / 10.0 + 1e3))
+                .unwrap();
+            let expected = (secs.saturating_sub(2)..=secs)
+                .map(|secs| (secs % 7) as Price / 10.0 + 1e3)
+                .fold(PartialAggregate::new(), |mut partial, price| {
+                    partial.add_price(price);
+                    partial
+                });
+
+            let average = aggregator.average(Timestamp::from_secs(secs));
+            assert_eq!(expected.finalize(), average.get(&NetworkName::N1).copied());
+        }
+
+        // an emptied window starts from a fresh sum
+        assert!(aggregator.average(Timestamp::from_secs(200)).is_empty());
+        aggregator.push(at(200, "S1", 0.1)).unwrap();
+        let average = aggregator.average(Timestamp::from_secs(200));
+        assert_eq!(HashMap::from([(NetworkName::N1, 0.1)]), average);
+    }
+
+    #[test]
+    fn windowed_aggregator_rejects_invalid_tickers() {
+        let mut aggregator = WindowedAggregator::new(Timestamp::from_secs(10));
+
+        assert!(aggregator.push(at(0, "BTC", 1.0)).is_err());
+        assert!(aggregator.push(at(0, "S1", Price::NAN)).is_err());
+        assert!(aggregator.average(Timestamp::ZERO).is_empty());
+    }
+}